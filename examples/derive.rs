@@ -1,16 +1,16 @@
-#![feature(trace_macros)]
+#![cfg_attr(trace_macros, feature(trace_macros))]
 #[macro_use]
 extern crate mac_derive;
 
 use std::marker::PhantomData;
 
+// the `cfg_attr` repeats `rename_all` on purpose, to exercise its handling
+#[allow(clippy::duplicated_attributes)]
+#[derive(Debug, EnumRepr)]
 #[enum_repr(rename_all = "UPPERCASE", other(Meta1, Meta2))]
 #[repr(C)]
-#[derive(Debug, EnumRepr)]
-#[enum_repr(default(PathBaseDefault))]
 #[cfg_attr(target_os = "linux", enum_repr(rename_all = "UPPERCASE"))]
 #[enum_repr(rename_all = "lowercase")]
-#[enum_repr(default = "Variant1")]
 #[enum_repr(attr(
     all(target_os = "linux", target_os = "macos"),
     attr_nest(nest1 = "nest1_lit", nest2 = "nest2_lit")
@@ -25,7 +25,6 @@ pub enum LittleEnum {
 #[derive(EnumRepr)]
 #[repr(u16)]
 #[enum_repr(rename_all = "lowercase")]
-#[enum_repr(default = "Variant1")]
 pub enum BigEnum {
     Variant1 = 10,
     Variant2 = 20,
@@ -37,6 +36,7 @@ type MyEnum = LittleEnum;
 #[cfg(target_endian = "big")]
 type MyEnum = BigEnum;
 
+#[cfg(trace_macros)]
 trace_macros!(true);
 mac_derive::make_enum! {
     #[derive(Debug, Copy, Clone)]
@@ -46,18 +46,141 @@ mac_derive::make_enum! {
     }
 }
 
+#[cfg(trace_macros)]
 trace_macros!(false);
 
+mac_derive::make_enum! {
+    #[derive(Debug)]
+    enum Flexible: u8 {
+        /// documented variant
+        First = 1,
+        Second(SECOND),
+        Third
+    }
+}
+
+mac_derive::make_enum! {
+    #[derive(Debug)]
+    pub enum Opcode: u8 columns(size: usize) from "examples/codes/opcodes.csv"
+}
+
+mac_derive::make_enum! {
+    #[derive(Debug)]
+    pub enum ErrorCode: u16 columns(desc: &'static str, retry: bool)
+        from "examples/codes/errors.toml"
+}
+
+#[mac_derive::awe(wire_enum)]
+pub enum Wire {
+    Ping = 1,
+    Pong = 2,
+}
+
+#[mac_derive::awe(flag_enum)]
+#[repr(u32)]
+pub enum Flag {
+    On = 1 << 20,
+    Off = 0,
+}
+
+#[derive(Debug, Default, Accessors)]
+pub struct Endpoint {
+    host: String,
+    #[enum_repr(getter = "copy", setter = "with")]
+    port: u16,
+}
+
+pub struct UserId(u64);
+
+forward_impl! {
+    impl Display, Debug, Hash, PartialEq, Eq, FromStr for UserId => 0: u64
+}
+
+#[derive(Debug, Clone, PartialEq, Newtype)]
+#[enum_repr(transparent, validate = "check_label")]
+pub struct Label(String);
+
+fn check_label(label: &str) -> Result<(), String> {
+    match label.is_empty() {
+        true => Err("empty label".to_owned()),
+        false => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Millis(u64);
+
+forward_impl! {
+    impl Display, ops(Add, Sub, Mul<u64>, AddAssign, Sum, checked(sub))
+        for Millis => 0
+}
+
+codec!();
+
+#[derive(Debug, PartialEq, Tagged, EnumRepr)]
+#[repr(u8)]
+#[enum_repr(content = "u16", kind = "FrameKind", rename_all = "lowercase")]
+pub enum Frame {
+    Hello { version: u16, name: String } = 1,
+    Data(Vec<u8>),
+    Bye,
+}
+
 fn main() {
-    let e = MyEnum::Variant1;
+    let _e = MyEnum::Variant1;
+    println!("{} {}", BigEnum::Variant2, u16::from(BigEnum::Variant1));
 
     let b = MakeEnum::Another;
 
     println!("{:?}", b);
+    println!("{:?} {:?}", Flexible::First, Flexible::Third);
+
+    println!("{:?} {}", Opcode::Load, opcode::HALT);
+    println!("{} {}", Opcode::Nop.size(), Opcode::Halt.size());
+    println!("{} {}", ErrorCode::NotFound.desc(), ErrorCode::Busy.retry());
+
+    println!("{:?} {}", Wire::Pong, Flag::On as u32);
+    assert_eq!(Wire::Ping, Wire::Ping.clone());
+
+    let mut endpoint = Endpoint::default().with_port(80);
+    endpoint.set_host("localhost".to_owned());
+    println!("{}:{}", endpoint.host(), endpoint.port());
+
+    let user: UserId = "7".parse().unwrap();
+    println!("{} {:?}", user, user);
+
+    let label: Label = "main".parse().unwrap();
+    println!("{} {:?}", label, Label::new(String::new()));
+
+    let mut elapsed = Millis(10) + Millis(5) * 2;
+    elapsed += Millis(1);
+    let total: Millis = [elapsed, Millis(4)].iter().sum();
+    println!("{} {:?}", total, Millis(1).checked_sub(total));
+
+    let frame = Frame::Hello {
+        version: 2,
+        name: "node".to_owned(),
+    };
+    println!("{} {}", frame.kind(), u8::from(frame.kind()));
+    let bytes = codec::Encode::to_bytes(&frame).unwrap();
+    assert_eq!(<Frame as codec::Decode>::from_bytes(&bytes), Ok(frame));
+    println!(
+        "{:?} {:?}",
+        bytes,
+        <Frame as codec::Decode>::from_bytes(&[9])
+    );
+
+    match Flexible::Second as u8 {
+        flexible::SECOND => println!("{:?}", Flexible::SECOND),
+        _ => unreachable!(),
+    }
     test_sym();
+    te();
 }
 
-const fn ident<T: Sized>(id: T) -> T { id }
+const fn ident<T: Sized>(id: T) -> T {
+    id
+}
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 struct Sym<T>(T);
@@ -79,7 +202,7 @@ macro_rules! imp {
 macro_rules! rev_imp {
         (impl $traits:ident into $($id:ident),*) => {
             $(
-            #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+            #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
             pub struct $id;
             impl $id { pub fn new() -> Self { $id }}
             impl traits::$traits for $id {}
@@ -109,7 +232,7 @@ impl Opt for () {
     type Color = ();
 }
 
-trait Opt: Sized {
+pub trait Opt: Sized {
     type Rename: traits::Rename;
     type Default: traits::Default;
     type Limit: traits::Limit;
@@ -130,6 +253,24 @@ impl Opt for DefOpt {
     type Color = ();
 }
 
+// the same with `impl_many!`, `WhiteOpt` overrides the color
+impl_many! {
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+    impl traits::Color for pub struct White, Grey;
+
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+    impl Opt {
+        type Rename = ();
+        type Default = ();
+        type Limit = ();
+        type Endian = ();
+        type Derive = ();
+        type Color = Grey;
+    } for pub struct GreyOpt, WhiteOpt {
+        type Color = White;
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Hash)]
 pub struct FullOpt<O: Opt> {
     rename: PhantomData<O::Rename>,
@@ -153,24 +294,23 @@ impl FullOpt<DefOpt> {
     }
 }
 
-use std::convert::identity;
-
 fn test_sym() {
     let s1 = SYM1;
     let id = ident(s1);
 
-    let a = FullOpt::new();
+    let _a = FullOpt::new();
+    let _b = FullOpt::<WhiteOpt>::default();
 
     println!("{:?} {:?}", s1, id);
 }
 fn te() {
-    let a = Red::new();
-    let b = Blue::new();
+    let _a = Red::new();
+    let _b = Blue::new();
 
     struct Color<N: traits::Color, P: traits::Color> {
         now: PhantomData<N>,
         _prev: PhantomData<P>,
-    };
+    }
 
     impl Color<Red, Red> {
         pub fn new() -> Color<Red, Red> {
@@ -209,9 +349,8 @@ fn te() {
         }
     }
 
-    let c = Color::new();
-    let r = c.r();
-    let b = c.b();
-    let g = c.g();
-    let y = c.c();
+    let _r = Color::new().r();
+    let _b = Color::new().b();
+    let _g = Color::new().g();
+    let _c = Color::new().c();
 }
@@ -0,0 +1,230 @@
+use ast::{
+    deny_unknown_fields, get_container_attrs, get_variant_attrs, AccessorsAttr,
+    FieldAttr,
+};
+use std::{collections::HashSet, fmt, str::FromStr};
+use syn::{
+    parse::{Parse, ParseStream},
+    Data, DeriveInput, Error, Fields, Generics, Ident, Result, Type,
+    Visibility,
+};
+
+/// `#[enum_repr(getter = "..")]`, what `fn field(&self)` returns
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Getter {
+    /// `&T`, the default
+    Ref,
+    /// `T` for `Copy` fields
+    Copy,
+    /// `T` through `Clone::clone`
+    Clone,
+    Skip,
+}
+
+/// `#[enum_repr(setter = "..")]`, which of `field_mut`, `set_field` and
+/// `with_field` are generated
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Setter {
+    /// all three, the default
+    All,
+    /// `field_mut` and `set_field`
+    Set,
+    /// `with_field` only
+    With,
+    Skip,
+}
+
+impl FromStr for Getter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ref" => Ok(Getter::Ref),
+            "copy" => Ok(Getter::Copy),
+            "clone" => Ok(Getter::Clone),
+            "skip" => Ok(Getter::Skip),
+            _ => err!(
+                r##"invalid getter: {}, expected #[enum_repr(getter = "ref | copy | clone | skip")]"##,
+                s
+            ),
+        }
+    }
+}
+
+impl FromStr for Setter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "all" => Ok(Setter::All),
+            "set" => Ok(Setter::Set),
+            "with" => Ok(Setter::With),
+            "skip" => Ok(Setter::Skip),
+            _ => err!(
+                r##"invalid setter: {}, expected #[enum_repr(setter = "all | set | with | skip")]"##,
+                s
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Getter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Getter::Ref => "ref",
+            Getter::Copy => "copy",
+            Getter::Clone => "clone",
+            Getter::Skip => "skip",
+        })
+    }
+}
+
+impl fmt::Display for Setter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Setter::All => "all",
+            Setter::Set => "set",
+            Setter::With => "with",
+            Setter::Skip => "skip",
+        })
+    }
+}
+
+/// ```
+///
+/// use mac_derive::Accessors;
+///
+/// #[derive(Accessors, Default)]
+/// #[enum_repr(vis = "pub(crate)")]
+/// pub struct Config {
+///     name: String,
+///     #[enum_repr(getter = "copy")]
+///     port: u16,
+///     #[enum_repr(getter = "copy", setter = "with", rename = "verbose")]
+///     is_verbose: bool,
+///     #[enum_repr(getter = "skip", setter = "skip")]
+///     cache: Vec<u8>,
+///     #[enum_repr(setter = "skip", vis = "pub")]
+///     id: u64,
+/// }
+///
+/// let mut config = Config::default().with_port(8080).with_verbose(true);
+/// config.set_name("local".to_owned());
+/// config.name_mut().push_str("host");
+///
+/// assert_eq!(config.name(), "localhost");
+/// assert_eq!(config.port(), 8080);
+/// assert!(config.verbose());
+/// assert_eq!(*config.id(), 0);
+///
+/// let config = config.with_verbose(false);
+/// assert!(!config.verbose());
+/// ```
+#[derive(Debug)]
+pub struct Accessors {
+    /// container defaults, overridden per field
+    pub attrs: HashSet<FieldAttr>,
+    pub ident: Ident,
+    pub generics: Generics,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug)]
+pub struct Field {
+    pub attrs: HashSet<FieldAttr>,
+    pub ident: Ident,
+    pub ty: Type,
+}
+
+impl Parse for Accessors {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let derive_input: DeriveInput = input.parse()?;
+        split_owned!(derive_input as attrs, ident, generics, data);
+
+        let fields = match data {
+            Data::Struct(data) => match data.fields {
+                Fields::Named(fields) => fields.named,
+                _ => {
+                    return err!(ident: "only work for structs with named fields")
+                },
+            },
+            _ => return err!(ident: "only work for struct"),
+        };
+
+        let strict = deny_unknown_fields(&attrs)?;
+        let attrs = get_container_attrs::<AccessorsAttr>(attrs)?
+            .into_iter()
+            .map(|AccessorsAttr(a)| a)
+            .collect();
+
+        let fields = fields
+            .into_iter()
+            .map(|field| {
+                Ok(Field {
+                    attrs: get_variant_attrs(field.attrs, strict)?,
+                    ident: field.ident.expect("named field"),
+                    ty: field.ty,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            attrs,
+            ident,
+            generics,
+            fields,
+        })
+    }
+}
+
+impl Accessors {
+    /// value of an attribute on `field`, else on the container
+    fn lookup<'a, T>(
+        &'a self,
+        field: &'a Field,
+        f: impl Fn(&'a FieldAttr) -> Option<T>,
+    ) -> Option<T> {
+        field
+            .attrs
+            .iter()
+            .find_map(&f)
+            .or_else(|| self.attrs.iter().find_map(&f))
+    }
+
+    pub fn getter(&self, field: &Field) -> Getter {
+        self.lookup(field, |a| match a {
+            FieldAttr::Getter(g) => Some(*g),
+            _ => None,
+        })
+        .unwrap_or(Getter::Ref)
+    }
+
+    pub fn setter(&self, field: &Field) -> Setter {
+        self.lookup(field, |a| match a {
+            FieldAttr::Setter(s) => Some(*s),
+            _ => None,
+        })
+        .unwrap_or(Setter::All)
+    }
+
+    /// visibility of the accessors of `field`, `pub` by default
+    pub fn vis(&self, field: &Field) -> Visibility {
+        self.lookup(field, |a| match a {
+            FieldAttr::Vis(vis) => Some(vis.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| parse_quote!(pub))
+    }
+
+    /// base name of the accessors, only a field may be renamed
+    pub fn name<'a>(&self, field: &'a Field) -> &'a Ident {
+        field
+            .attrs
+            .iter()
+            .find_map(|a| match a {
+                FieldAttr::Rename(ident) => Some(ident),
+                _ => None,
+            })
+            .unwrap_or(&field.ident)
+    }
+}
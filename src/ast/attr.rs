@@ -1,18 +1,18 @@
 use ast::{
-    get_meta_items, get_meta_items2, token_from_lit, RenameRule, ATTR,
-    ATTR_NEST, DEFAULT, DISCR, FROM, INTO, OTHER, RENAME, RENAME_ALL, TRY_FROM,
+    parse_lit_into_path, parse_lit_str_into_ty, token_from_lit, Getter,
+    RenameRule, Setter, Symbol, ATTR, ATTR_NEST, CONTENT, CRATE, DEFAULT,
+    DEREF, DISCR, ERROR, FLATTEN, FROM, GETTER, INTO, KIND, OTHER, RENAME,
+    RENAME_ALL, SEPARATOR, SETTER, SKIP, SKIP_DISPLAY, SKIP_HIDDEN, SKIP_ITER,
+    SKIP_PARSE, TAG, TRANSPARENT, TRY_FROM, VALIDATE, VARIANT_IDENTIFIER, VIS,
+    WITH,
 };
-use proc_macro2::{Literal, TokenStream, TokenTree};
 use quote::ToTokens;
 use std::{
     collections::HashSet, convert::TryFrom, marker::PhantomData, str::FromStr,
 };
 use syn::{
-    export::Hash,
-    parse::{Parse, ParseStream},
-    spanned::Spanned,
-    Attribute, Error, Ident, Lit, LitStr, Meta, MetaList, MetaNameValue,
-    NestedMeta, Result,
+    export::Hash, spanned::Spanned, Error, Ident, Lit, LitStr, Meta, MetaList,
+    MetaNameValue, NestedMeta, Path, Result, Type, Visibility,
 };
 
 pub trait AttributesBucket
@@ -21,19 +21,42 @@ where
         + Hash
         + Eq
         + TryFrom<MetaList, Error = Error>
-        + TryFrom<MetaNameValue, Error = Error>,
+        + TryFrom<MetaNameValue, Error = Error>
+        + TryFrom<Path, Error = Error>,
 {
+    /// `container`, `variant` or `field`, for errors
+    const POSITION: &'static str;
+    /// keys accepted in this position
+    const KEYS: &'static [Symbol];
+    /// keys of the other derives that may sit on the same item, left to them
+    const SHARED: &'static [Symbol] = &[];
 }
 
 macro_rules! make_attr_enum {
     (
-
         pub enum $id:ident {
             $(
                 $num:tt: $field:ident( $( $argv:ty ),+ )
             ),+
             $(,)*
         }
+    ) => {
+        #[allow(dead_code)]
+        #[derive(Debug, Eq, PartialEq, Hash)]
+        pub enum $id {
+            $(
+                $field( $( $argv ),+ )
+            ),+
+        }
+    };
+
+    (
+        pub enum $id:ident in $position:literal [ $( $key:ident ),+ $(,)* ] {
+            $(
+                $num:tt: $field:ident( $( $argv:ty ),+ )
+            ),+
+            $(,)*
+        }
     ) => {
         #[allow(dead_code)]
         #[derive(Debug, Eq, PartialEq, Hash)]
@@ -44,10 +67,14 @@ macro_rules! make_attr_enum {
         }
 
         impl AttributesBucket for $id {
+            const POSITION: &'static str = $position;
+            const KEYS: &'static [Symbol] = &[ $( $key ),+ ];
         }
     };
 }
 
+// container attributes of every derive, each accepts the keys of its own
+// bucket below
 make_attr_enum! {
     pub enum ContainerAttr {
         0: RenameAll(RenameRule),
@@ -58,7 +85,34 @@ make_attr_enum! {
         5: Attr(String),
         6: From(Ident),
         7: Into(Ident),
-        8: TryFrom(Ident)
+        8: TryFrom(Ident),
+        9: Crate(Path),
+        10: Deref(PhantomData<bool>),
+        11: Validate(Path),
+        12: Error(Box<Type>),
+        13: Tag(Ident),
+        14: Content(Ident),
+        15: Kind(Ident),
+        16: SkipHidden(PhantomData<bool>),
+        17: With(Path),
+        18: Separator(String)
+    }
+}
+
+impl TryFrom<Path> for ContainerAttr {
+    type Error = Error;
+
+    fn try_from(m: Path) -> Result<Self> {
+        match &m {
+            id if id == TRANSPARENT => Ok(ContainerAttr::Trans(PhantomData)),
+            id if id == DEREF => Ok(ContainerAttr::Deref(PhantomData)),
+            id if id == SKIP_HIDDEN => {
+                Ok(ContainerAttr::SkipHidden(PhantomData))
+            },
+            _ => {
+                err!(m: "unexpected container attribute {}", m.to_token_stream())
+            },
+        }
     }
 }
 
@@ -82,7 +136,8 @@ impl TryFrom<MetaList> for ContainerAttr {
                 let lts = list.to_token_stream();
                 Ok(ContainerAttr::TryFrom(syn::parse2::<Ident>(lts)?))
             },
-            (id, list) if id == ATTR || id == ATTR_NEST || id == OTHER => {
+            (id, _) if id == ATTR || id == ATTR_NEST || id == OTHER => {
+                check_other(&m)?;
                 Ok(ContainerAttr::Other(m.to_token_stream().to_string()))
             },
             _ => {
@@ -105,16 +160,49 @@ impl TryFrom<MetaNameValue> for ContainerAttr {
                     lit.value().as_str(),
                 )?))
             },
-            (id, _) if (id == DEFAULT || id == RENAME_ALL) => err!(
-                r##"expected attribute value {}, expected {} = "str literal""##,
-                &m.to_token_stream(),
-                &m.path.to_token_stream()
-            ),
-            (id, _) if id == OTHER => {
-                Ok(ContainerAttr::Other(m.to_token_stream().to_string()))
+            (id, Lit::Str(lit)) if id == CRATE => {
+                Ok(ContainerAttr::Crate(parse_lit_into_path(lit)?))
+            },
+            (id, Lit::Str(lit)) if id == VALIDATE => {
+                Ok(ContainerAttr::Validate(parse_lit_into_path(lit)?))
+            },
+            (id, Lit::Str(lit)) if id == WITH => {
+                Ok(ContainerAttr::With(parse_lit_into_path(lit)?))
+            },
+            (id, Lit::Str(lit)) if id == SEPARATOR => {
+                Ok(ContainerAttr::Separator(lit.value()))
+            },
+            (id, Lit::Str(lit)) if id == ERROR => {
+                Ok(ContainerAttr::Error(Box::new(parse_lit_str_into_ty(lit)?)))
+            },
+            (id, Lit::Str(lit)) if id == TAG => {
+                Ok(ContainerAttr::Tag(int_ident(lit, INTS)?))
+            },
+            (id, Lit::Str(lit)) if id == CONTENT => {
+                Ok(ContainerAttr::Content(int_ident(lit, &INTS[..5])?))
             },
-            (id, _) if id == ATTR => {
-                Ok(ContainerAttr::Attr(m.to_token_stream().to_string()))
+            (id, lit) if id == KIND || id == VARIANT_IDENTIFIER => {
+                Ok(ContainerAttr::Kind(token_from_lit(lit)?))
+            },
+            (id, _)
+                if (id == DEFAULT
+                    || id == RENAME_ALL
+                    || id == CRATE
+                    || id == VALIDATE
+                    || id == WITH
+                    || id == SEPARATOR
+                    || id == ERROR
+                    || id == TAG
+                    || id == CONTENT) =>
+            {
+                err!(
+                    r##"expected attribute value {}, expected {} = "str literal""##,
+                    &m.to_token_stream(),
+                    &m.path.to_token_stream()
+                )
+            },
+            (id, _) if id == OTHER || id == ATTR => {
+                err!(m: "expected `{}(..)`", m.path.to_token_stream())
             },
             _ => {
                 err!(m: r##"unexpected container attribute {}"##, m.to_token_stream())
@@ -123,20 +211,101 @@ impl TryFrom<MetaNameValue> for ContainerAttr {
     }
 }
 
+/// integer types of `tag`, the unsigned ones first for `content`
+const INTS: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128",
+];
+
+/// `other(..)` lists marker paths and `attr(..)` meta items, both carried
+/// along unread, so only their shape is checked
+fn check_other(m: &MetaList) -> Result<()> {
+    let key = m.path.to_token_stream();
+    if m.nested.is_empty() {
+        return err!(m: "`{}(..)` is empty", key);
+    }
+
+    for nested in &m.nested {
+        match nested {
+            NestedMeta::Meta(Meta::Path(_)) => {},
+            NestedMeta::Meta(_) if m.path != OTHER => {},
+            _ => {
+                return err!(
+                    nested: "unexpected `{}` in `{}(..)`, expected {}",
+                    nested.to_token_stream(),
+                    key,
+                    match m.path == OTHER {
+                        true => "a path",
+                        false => "a meta item",
+                    }
+                )
+            },
+        }
+    }
+
+    Ok(())
+}
+
+fn int_ident(lit: &LitStr, allowed: &[&str]) -> Result<Ident> {
+    let value = lit.value();
+    if allowed.contains(&value.as_str()) {
+        Ok(Ident::new(&value, lit.span()))
+    } else {
+        err!(lit: "expected one of {}", allowed.join(", "))
+    }
+}
+
 make_attr_enum! {
-    pub enum VariantAttr {
+    pub enum VariantAttr in "variant" [
+        RENAME, DISCR, SKIP, SKIP_PARSE, SKIP_DISPLAY, SKIP_ITER, WITH, FLATTEN,
+        OTHER,
+    ] {
         0: Default(bool),
         1: Discriminant(String),
         2: Rename(RenameRule),
         3: Trans(PhantomData<bool>),
-        4: Other(String)
+        4: Other(String),
+        5: Skip(Skip),
+        6: With(Path),
+        7: Flatten(PhantomData<bool>)
     }
 }
 
-pub struct VarAttr<'a> {
-    pub display: &'a Ident,
-    pub rename: &'a LitStr,
-    pub default: bool,
+/// generated APIs a variant is left out of
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Skip {
+    /// every one below, and `TryFrom` of the repr
+    All,
+    /// `FromStr`
+    Parse,
+    /// `NAMES`, `Display` writes the variant identifier
+    Display,
+    /// `VARIANTS`, `COUNT` and `iter()`
+    Iter,
+}
+
+/// string name of a variant: its own `rename`, else the container
+/// `rename_all`, else the ident itself
+pub fn variant_name(
+    container: &HashSet<ContainerAttr>,
+    variant: &HashSet<VariantAttr>,
+    ident: &Ident,
+) -> String {
+    let rule = variant
+        .iter()
+        .filter_map(|a| match a {
+            VariantAttr::Rename(rule) => Some(rule),
+            _ => None,
+        })
+        .chain(container.iter().filter_map(|a| match a {
+            ContainerAttr::RenameAll(rule) => Some(rule),
+            _ => None,
+        }))
+        .next();
+
+    match rule {
+        Some(rule) => rule.apply_to_variant(&ident.to_string()),
+        None => ident.to_string(),
+    }
 }
 
 impl TryFrom<MetaNameValue> for VariantAttr {
@@ -151,26 +320,172 @@ impl TryFrom<MetaNameValue> for VariantAttr {
             (id, Lit::Str(s)) if id == RENAME => Ok(VariantAttr::Rename(
                 RenameRule::from_str(s.value().as_str())?,
             )),
-            (id, _) if (id == RENAME || id == DISCR) => err!(
+            (id, Lit::Str(s)) if id == WITH => {
+                Ok(VariantAttr::With(parse_lit_into_path(s)?))
+            },
+            (id, _) if (id == RENAME || id == DISCR || id == WITH) => err!(
                 m: r##"unexpected attribute value {}, expected {} = "str literal""##,
                 &m.to_token_stream(),
                 &m.path.to_token_stream()
             ),
-            (id, _) if id == OTHER => {
-                Ok(VariantAttr::Other(m.to_token_stream().to_string()))
-            },
+            (id, _) if id == OTHER => err!(m: "expected `other(..)`"),
             _ => err!(m: "unexpected attribute {:?}", &m.to_token_stream()),
         }
     }
 }
 
+impl TryFrom<Path> for VariantAttr {
+    type Error = Error;
+
+    fn try_from(m: Path) -> Result<Self> {
+        match &m {
+            id if id == SKIP => Ok(VariantAttr::Skip(Skip::All)),
+            id if id == SKIP_PARSE => Ok(VariantAttr::Skip(Skip::Parse)),
+            id if id == SKIP_DISPLAY => Ok(VariantAttr::Skip(Skip::Display)),
+            id if id == SKIP_ITER => Ok(VariantAttr::Skip(Skip::Iter)),
+            id if id == FLATTEN => Ok(VariantAttr::Flatten(PhantomData)),
+            _ => err!(m: "unexpected attribute {}", m.to_token_stream()),
+        }
+    }
+}
+
 impl TryFrom<MetaList> for VariantAttr {
     type Error = Error;
 
     fn try_from(m: MetaList) -> Result<Self> {
         match (&m.path, &m.nested) {
-            (id, list) if id == OTHER => Ok(VariantAttr::Trans(PhantomData)),
+            (id, _) if id == OTHER => {
+                check_other(&m)?;
+                Ok(VariantAttr::Other(m.to_token_stream().to_string()))
+            },
             _ => err!(m: "unexpected attribute {:?}", m.to_token_stream()),
         }
     }
 }
+
+make_attr_enum! {
+    pub enum FieldAttr in "field" [GETTER, SETTER, RENAME, VIS] {
+        0: Getter(Getter),
+        1: Setter(Setter),
+        2: Rename(Ident),
+        3: Vis(Visibility)
+    }
+}
+
+impl TryFrom<MetaNameValue> for FieldAttr {
+    type Error = Error;
+
+    fn try_from(m: MetaNameValue) -> Result<Self> {
+        match (&m.path, &m.lit) {
+            (id, Lit::Str(s)) if id == GETTER => {
+                Ok(FieldAttr::Getter(Getter::from_str(s.value().as_str())?))
+            },
+            (id, Lit::Str(s)) if id == SETTER => {
+                Ok(FieldAttr::Setter(Setter::from_str(s.value().as_str())?))
+            },
+            (id, lit) if id == RENAME => {
+                Ok(FieldAttr::Rename(token_from_lit(lit)?))
+            },
+            (id, lit) if id == VIS => Ok(FieldAttr::Vis(token_from_lit(lit)?)),
+            _ => err!(m: "unexpected field attribute {}", m.to_token_stream()),
+        }
+    }
+}
+
+impl TryFrom<Path> for FieldAttr {
+    type Error = Error;
+
+    fn try_from(m: Path) -> Result<Self> {
+        err!(m: "unexpected field attribute {}", m.to_token_stream())
+    }
+}
+
+impl TryFrom<MetaList> for FieldAttr {
+    type Error = Error;
+
+    fn try_from(m: MetaList) -> Result<Self> {
+        err!(m: "unexpected field attribute {}", m.to_token_stream())
+    }
+}
+
+/// buckets accepting the keys of one derive out of a shared attribute enum
+macro_rules! make_attr_keys {
+    ($(
+        $( #[ $meta:meta ] )*
+        pub struct $id:ident($attr:ident) in $position:literal [
+            $( $key:ident ),+ $(,)*
+        ] shared [
+            $( $shared:ident ),+ $(,)*
+        ];
+    )+) => {$(
+        $( #[ $meta ] )*
+        #[derive(Debug, Eq, PartialEq, Hash)]
+        pub struct $id(pub $attr);
+
+        impl AttributesBucket for $id {
+            const POSITION: &'static str = $position;
+            const KEYS: &'static [Symbol] = &[ $( $key ),+ ];
+            const SHARED: &'static [Symbol] = &[ $( $shared ),+ ];
+        }
+
+        impl TryFrom<MetaNameValue> for $id {
+            type Error = Error;
+
+            fn try_from(m: MetaNameValue) -> Result<Self> {
+                $attr::try_from(m).map($id)
+            }
+        }
+
+        impl TryFrom<Path> for $id {
+            type Error = Error;
+
+            fn try_from(m: Path) -> Result<Self> {
+                $attr::try_from(m).map($id)
+            }
+        }
+
+        impl TryFrom<MetaList> for $id {
+            type Error = Error;
+
+            fn try_from(m: MetaList) -> Result<Self> {
+                $attr::try_from(m).map($id)
+            }
+        }
+    )+};
+}
+
+make_attr_keys! {
+    /// `#[derive(EnumRepr)]` and `make_enum!` container keys, `Tagged` may
+    /// share the enum
+    pub struct EnumReprAttr(ContainerAttr) in "EnumRepr container" [
+        RENAME_ALL, CRATE, KIND, VARIANT_IDENTIFIER, SKIP_HIDDEN, WITH,
+        SEPARATOR, OTHER, ATTR,
+    ] shared [
+        TAG, CONTENT,
+    ];
+
+    /// `#[derive(Newtype)]` container keys, `Tagged` and `Accessors` may
+    /// share the struct
+    pub struct NewtypeAttr(ContainerAttr) in "Newtype container" [
+        TRANSPARENT, DEREF, VALIDATE, ERROR,
+    ] shared [
+        TAG, CONTENT, CRATE, GETTER, SETTER, VIS,
+    ];
+
+    /// `#[derive(Tagged)]` container keys, `EnumRepr` may share an enum,
+    /// `Newtype` and `Accessors` a struct
+    pub struct TaggedAttr(ContainerAttr) in "Tagged container" [
+        TAG, CONTENT, CRATE,
+    ] shared [
+        RENAME_ALL, KIND, VARIANT_IDENTIFIER, SKIP_HIDDEN, WITH, SEPARATOR,
+        OTHER, ATTR, TRANSPARENT, DEREF, VALIDATE, ERROR, GETTER, SETTER, VIS,
+    ];
+
+    /// container defaults of `#[derive(Accessors)]`, the field keys but
+    /// `rename`, `Newtype` and `Tagged` may share the struct
+    pub struct AccessorsAttr(FieldAttr) in "Accessors container" [
+        GETTER, SETTER, VIS,
+    ] shared [
+        TRANSPARENT, DEREF, VALIDATE, ERROR, TAG, CONTENT, CRATE,
+    ];
+}
@@ -8,11 +8,66 @@ pub enum RenameRule {
     Pascal,
     Snake,
     Scream,
+    Kebab,
 }
 
 impl RenameRule {
     const ALL: &'static str =
-        "UPPERCASE | lowercase | PascalCase | snake_case | SCREAMING_SNAKE_CASE";
+        "UPPERCASE | lowercase | PascalCase | snake_case | SCREAMING_SNAKE_CASE \
+         | kebab-case";
+
+    /// apply the rule to a PascalCase variant (or type) ident
+    pub fn apply_to_variant(&self, variant: &str) -> String {
+        match self {
+            RenameRule::Pascal => variant.to_owned(),
+            RenameRule::Lower => variant.to_ascii_lowercase(),
+            RenameRule::Upper => variant.to_ascii_uppercase(),
+            RenameRule::Snake => words(variant).join("_").to_lowercase(),
+            RenameRule::Scream => words(variant).join("_").to_uppercase(),
+            RenameRule::Kebab => RenameRule::Snake
+                .apply_to_variant(variant)
+                .replace('_', "-"),
+        }
+    }
+}
+
+/// words of a PascalCase ident, a run of capitals is one word so `HTTPError`
+/// is `HTTP` and `Error`, and `_` separates words
+fn words(ident: &str) -> Vec<&str> {
+    let chars = ident.char_indices().collect::<Vec<_>>();
+    let mut words = Vec::new();
+    let mut start = 0;
+
+    for (n, &(i, ch)) in chars.iter().enumerate() {
+        if ch == '_' {
+            if i > start {
+                words.push(&ident[start..i]);
+            }
+            start = i + 1;
+            continue;
+        }
+
+        let prev = n.checked_sub(1).map(|p| chars[p].1);
+        let next = chars.get(n + 1).map(|&(_, c)| c);
+        let boundary = ch.is_uppercase()
+            && match prev {
+                Some(p) if p.is_lowercase() || p.is_numeric() => true,
+                Some(p) if p.is_uppercase() => {
+                    next.is_some_and(|c| c.is_lowercase())
+                },
+                _ => false,
+            };
+
+        if boundary && i > start {
+            words.push(&ident[start..i]);
+            start = i;
+        }
+    }
+
+    if start < ident.len() {
+        words.push(&ident[start..]);
+    }
+    words
 }
 
 impl FromStr for RenameRule {
@@ -25,6 +80,7 @@ impl FromStr for RenameRule {
             "PascalCase" => Ok(RenameRule::Pascal),
             "snake_case" => Ok(RenameRule::Snake),
             "SCREAMING_SNAKE_CASE" => Ok(RenameRule::Scream),
+            "kebab-case" => Ok(RenameRule::Kebab),
             _ => err!(
                 r##"invalid rename attribute: {}, expected #[enum_repr(rename = "{}")]"##,
                 s,
@@ -42,6 +98,33 @@ impl fmt::Display for RenameRule {
             RenameRule::Pascal => "PascalCase",
             RenameRule::Snake => "snake_case",
             RenameRule::Scream => "SCREAMING_SNAKE_CASE",
+            RenameRule::Kebab => "kebab-case",
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::RenameRule;
+
+    fn apply(rule: RenameRule, variant: &str) -> String {
+        rule.apply_to_variant(variant)
+    }
+
+    #[test]
+    fn acronyms_are_one_word() {
+        assert_eq!(apply(RenameRule::Snake, "HTTPError"), "http_error");
+        assert_eq!(apply(RenameRule::Snake, "GetHTTP"), "get_http");
+        assert_eq!(apply(RenameRule::Scream, "IOError"), "IO_ERROR");
+        assert_eq!(apply(RenameRule::Kebab, "ParseURLFast"), "parse-url-fast");
+    }
+
+    #[test]
+    fn plain_words() {
+        assert_eq!(apply(RenameRule::Snake, "NotFound"), "not_found");
+        assert_eq!(apply(RenameRule::Snake, "V2Beta"), "v2_beta");
+        assert_eq!(apply(RenameRule::Snake, "A"), "a");
+        assert_eq!(apply(RenameRule::Snake, "Already_Snake"), "already_snake");
+        assert_eq!(apply(RenameRule::Scream, "MakeEnum"), "MAKE_ENUM");
+    }
+}
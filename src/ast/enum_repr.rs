@@ -1,127 +1,367 @@
 use ast::{
-    get_all_attrs, get_container_attrs, get_variant_attrs, ContainerAttr,
+    deny_unknown_fields, get_container_attrs, get_variant_attrs, repr_int,
+    ContainerAttr, EnumReprAttr, Numbering, RenameRule, Skip, SourceMacro,
     VariantAttr,
 };
-use quote::ToTokens;
-use std::{collections::HashSet, convert::TryFrom};
+use std::collections::HashSet;
 use syn::{
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
     token, Attribute, Data, DeriveInput, Error, Expr, Fields, Generics, Ident,
-    Meta, Result, Visibility,
+    Meta, NestedMeta, Path, Result, Type, Visibility,
 };
 
-/// ```no_run
+pub mod kw {
+    custom_keyword!(columns);
+    custom_keyword!(from);
+}
+
+/// ```
 ///
 /// use mac_derive::EnumRepr;
+/// use std::convert::TryFrom;
 ///
-/// #[derive(EnumRepr)]
+/// #[derive(EnumRepr, Debug, PartialEq)]
+/// #[repr(u8)]
 /// #[enum_repr(rename_all = "UPPERCASE")]
 /// pub enum EnumIdent {
 ///     Var1,
-///     #[enum_repr(from = "FromType")]
+///     #[enum_repr(rename = "lowercase")]
 ///     Var2 = 12,
 ///     Etc
 /// }
+///
+/// // a unit-only `MessageKind` with the same treatment, and `kind()`
+/// #[derive(EnumRepr)]
+/// #[enum_repr(kind = "MessageKind", rename_all = "snake_case")]
+/// pub enum Message {
+///     Ping,
+///     Text(String),
+///     Move { x: i32, y: i32 },
+/// }
+///
+/// fn main() {
+///     assert_eq!(u8::from(EnumIdent::Etc), 13);
+///     assert_eq!(EnumIdent::try_from(12), Ok(EnumIdent::Var2));
+///     assert_eq!(EnumIdent::try_from(2), Err(2));
+///
+///     assert_eq!(EnumIdent::Var2.name(), "var2");
+///     assert_eq!("VAR1".parse(), Ok(EnumIdent::Var1));
+///     assert_eq!(EnumIdent::NAMES, &["VAR1", "var2", "ETC"]);
+///     assert_eq!(EnumIdent::iter().count(), EnumIdent::COUNT);
+///
+///     let text = Message::Text("hi".to_owned());
+///     assert_eq!(text.kind(), MessageKind::Text);
+///     assert_eq!(text.kind().to_string(), "text");
+///     assert_eq!(MessageKind::VARIANTS.len(), 3);
+///     assert_eq!(
+///         MessageKind::iter().collect::<Vec<_>>(),
+///         [MessageKind::Ping, MessageKind::Text, MessageKind::Move]
+///     );
+///     assert_eq!("move".parse(), Ok(MessageKind::Move));
+/// }
+/// ```
+///
+/// unit-only enums get:
+///
+/// - `From<Enum>` for the `#[repr(..)]` integer, `isize` without one, and
+///   `TryFrom` of it, failing with the unmatched value
+/// - `const fn as_str()`, `name()`, `Display`, `AsRef<str>` and `From` for
+///   `&'static str` and `Cow<'static, str>` using the renamed variant names
+/// - `TryFrom<&str>` and `TryFrom<String>` failing with the input, and
+///   `FromStr` borrowing through them with a `&'static str` error, so
+///   nothing allocates
+/// - `VARIANTS`, `NAMES`, `COUNT` and `iter()` in declaration order
+///
+/// enums with fields get them on the `kind = "Name"` companion, and nothing
+/// without it
+///
+/// variants can be left out of the generated APIs, matches stay exhaustive
+///
+/// - `skip_parse`: `FromStr`
+/// - `skip_display`: `NAMES`, the name becomes the variant identifier
+/// - `skip_iter`: `VARIANTS`, `COUNT` and `iter()`
+/// - `skip`: all of them and `TryFrom` of the repr
+///
+/// `skip_hidden` on the enum skips the `#[doc(hidden)]` variants
+///
+/// `with = "path"` on a variant, or the whole enum, formats and parses it
+/// through `path::to_str(&Enum)`, returning a `String` or `&'static str`, and
+/// `path::from_str(&str) -> Option<Enum>`, whose results are kept only for
+/// the variants using `path` and parsed; `From` for `Cow<'static, str>`
+/// follows `Display`, while `name()`, `as_str()`, `NAMES`, `AsRef<str>` and
+/// `From` for `&'static str` keep the static names
+///
+/// ```
+///
+/// use mac_derive::EnumRepr;
+/// use std::borrow::Cow;
+///
+/// mod version {
+///     use super::Api;
+///
+///     pub fn to_str(api: &Api) -> String {
+///         format!("v{}.0", *api as u8)
+///     }
+///
+///     pub fn from_str(s: &str) -> Option<Api> {
+///         match s {
+///             "v1.0" => Some(Api::V1),
+///             "v2.0" => Some(Api::V2),
+///             "v3.0" => Some(Api::V3),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// #[derive(EnumRepr, Debug, PartialEq, Clone, Copy)]
+/// #[repr(u8)]
+/// #[enum_repr(rename_all = "lowercase")]
+/// pub enum Api {
+///     Latest = 0,
+///     #[enum_repr(with = "version")]
+///     V1,
+///     #[enum_repr(with = "version")]
+///     V2,
+///     #[enum_repr(with = "version", skip_parse)]
+///     V3,
+/// }
+///
+/// fn main() {
+///     assert_eq!(Api::V2.to_string(), "v2.0");
+///     assert_eq!(Api::V2.as_str(), "v2");
+///     assert_eq!(Cow::from(Api::V1), "v1.0");
+///     assert!(matches!(Cow::from(Api::Latest), Cow::Borrowed("latest")));
+///     let name: &'static str = Api::V1.into();
+///     assert_eq!(name, "v1");
+///     assert_eq!(Api::V3.as_ref(), "v3");
+///     assert_eq!("v1.0".parse(), Ok(Api::V1));
+///     assert_eq!("v2.0".parse(), Ok(Api::V2));
+///     assert_eq!(Api::Latest.to_string(), "latest");
+///     assert!("v3.0".parse::<Api>().is_err());
+///     assert_eq!("latest".parse(), Ok(Api::Latest));
+/// }
+/// ```
+///
+/// `flatten` on a variant with one unnamed field of another `EnumRepr` type
+/// joins the nested names and variants into the enum: `iter()` and `COUNT`
+/// cover every nested variant, `Display` and parsing go through the nested
+/// impls with the names joined by `separator = ".."`, `.` by default; the
+/// other variants must be unit and there is no `VARIANTS`, `NAMES`, `as_str`
+/// or repr conversion
+///
+/// ```
+///
+/// use mac_derive::EnumRepr;
+///
+/// #[derive(EnumRepr, Debug, PartialEq, Clone, Copy)]
+/// #[enum_repr(rename_all = "snake_case")]
+/// pub enum IoError {
+///     NotFound,
+///     Denied,
+/// }
+///
+/// #[derive(EnumRepr, Debug, PartialEq, Clone, Copy)]
+/// #[enum_repr(rename_all = "snake_case")]
+/// pub enum DbError {
+///     Locked,
+/// }
+///
+/// #[derive(EnumRepr, Debug, PartialEq, Clone, Copy)]
+/// #[enum_repr(rename_all = "snake_case")]
+/// pub enum Error {
+///     #[enum_repr(flatten)]
+///     Io(IoError),
+///     #[enum_repr(flatten)]
+///     Db(DbError),
+///     Other,
+/// }
+///
+/// fn main() {
+///     assert_eq!(Error::COUNT, 4);
+///     assert_eq!(Error::Io(IoError::NotFound).to_string(), "io.not_found");
+///     assert_eq!("db.locked".parse(), Ok(Error::Db(DbError::Locked)));
+///     assert_eq!(
+///         Error::iter().collect::<Vec<_>>(),
+///         [
+///             Error::Io(IoError::NotFound),
+///             Error::Io(IoError::Denied),
+///             Error::Db(DbError::Locked),
+///             Error::Other,
+///         ]
+///     );
+///     assert_eq!("io.not_found".parse(), Ok(Error::Io(IoError::NotFound)));
+///     assert_eq!(Error::Other.to_string(), "other");
+///     assert!("io.locked".parse::<Error>().is_err());
+/// }
+/// ```
+///
+/// unknown keys are errors naming the closest known one,
+/// `deny_unknown_fields = false` on the container ignores them instead;
+/// `other(..)` takes paths and `attr(..)` meta items, both are kept unread
+///
+/// ```
+///
+/// use mac_derive::EnumRepr;
+///
+/// #[derive(EnumRepr, Debug, PartialEq)]
+/// #[enum_repr(rename_all = "lowercase", skip_hidden)]
+/// pub enum Level {
+///     Low,
+///     High,
+///     #[enum_repr(skip_iter)]
+///     Max,
+///     #[doc(hidden)]
+///     __NonExhaustive,
+/// }
+///
+/// fn main() {
+///     assert_eq!(Level::VARIANTS, &[Level::Low, Level::High]);
+///     assert_eq!(Level::COUNT, 2);
+///     assert_eq!(Level::iter().last(), Some(Level::High));
+///     assert_eq!("max".parse(), Ok(Level::Max));
+///     assert!("__nonexhaustive".parse::<Level>().is_err());
+/// }
 /// ```
 #[derive(Debug)]
 pub struct Enum {
     pub attrs: HashSet<ContainerAttr>,
+    pub vis: Visibility,
     pub ident: Ident,
     pub generics: Generics,
+    /// integer of `#[repr(..)]`
+    pub repr: Option<Ident>,
     pub variants: Vec<Variant>,
 }
 
 #[derive(Debug)]
 pub struct Variant {
     pub attrs: HashSet<VariantAttr>,
+    /// `#[cfg(..)]` attributes, repeated on every generated arm
+    pub cfgs: Vec<Attribute>,
+    /// `#[doc(hidden)]`
+    pub hidden: bool,
     pub ident: Ident,
     pub fields: Fields,
+    pub discriminant: Option<Expr>,
 }
 
 impl Parse for Enum {
     fn parse(input: ParseStream) -> Result<Self> {
         let derive_input: DeriveInput = input.parse()?;
-        split_owned!(derive_input as attrs, ident, generics, data);
+        split_owned!(derive_input as attrs, vis, ident, generics, data);
 
         let data = match data {
             Data::Enum(data) => data,
             _ => return Err(Error::new(ident.span(), "only work for enum")),
         };
 
-        let content = get_all_attrs(&attrs)?
+        let repr = repr_int(&attrs);
+        let strict = deny_unknown_fields(&attrs)?;
+        let attrs = get_container_attrs::<EnumReprAttr>(attrs)?
             .into_iter()
-            .map(|m| match &m {
-                Meta::Path(p) => format!(
-                    "\n\n{:->100}\nTOKEN: {}\nTREE:{:?}\nVAL: {}\nTYPE: PATH\n",
-                    "-",
-                    m.to_token_stream(),
-                    m.path().get_ident(),
-                    p.to_token_stream()
-                ),
-                Meta::List(l) => format!(
-                    "\n\n{:->100}\nTOKEN: {}\nTREE: {:?}\nVAL: {}\nTYPE: LIST\n",
-                    "-",
-                    m.to_token_stream(),
-                    m.path().get_ident(),
-                    l.to_token_stream()
-                ),
-                Meta::NameValue(n) => format!(
-                    "\n\n{:->100}\nTOKEN: {}\nTREE: {:?}\nVAL: {}\nTYPE: NVAL\n",
-                    "-",
-                    m.to_token_stream(),
-                    m.path().get_ident(),
-                    n.to_token_stream()
-                ),
-            })
-            .collect::<String>();
-
-        use std::{
-            fs::{write, File, OpenOptions},
-            io::Write,
-        };
-
-        let mut file = OpenOptions::new()
-            .create(true)
-            .truncate(false)
-            .write(true)
-            .open("/tmp/parse.rs")
-            .unwrap();
-
-        file.write_all(content.as_bytes()).unwrap();
-
-        let attrs = get_container_attrs(attrs)?;
+            .map(|EnumReprAttr(a)| a)
+            .collect();
         let variants = data
             .variants
             .into_iter()
-            .flat_map(Variant::try_from)
-            .collect();
+            .map(|var| Variant::new(var, strict))
+            .collect::<Result<_>>()?;
 
         Ok(Self {
             attrs,
+            vis,
             ident,
             generics,
+            repr,
             variants,
         })
     }
 }
 
-impl TryFrom<syn::Variant> for Variant {
-    type Error = Error;
+impl Enum {
+    /// `var` is left out of the `skip` API, `#[doc(hidden)]` variants too
+    /// with `skip_hidden`
+    pub fn skips(&self, var: &Variant, skip: Skip) -> bool {
+        let hidden = var.hidden
+            && self
+                .attrs
+                .iter()
+                .any(|a| matches!(a, ContainerAttr::SkipHidden(_)));
+
+        hidden
+            || var.attrs.iter().any(|a| match a {
+                VariantAttr::Skip(s) => *s == Skip::All || *s == skip,
+                _ => false,
+            })
+    }
+
+    /// module with the `to_str` and `from_str` of `var`, its own or the enum
+    /// one
+    pub fn with<'a>(&'a self, var: &'a Variant) -> Option<&'a Path> {
+        let own = var.attrs.iter().find_map(|a| match a {
+            VariantAttr::With(path) => Some(path),
+            _ => None,
+        });
+
+        own.or_else(|| {
+            self.attrs.iter().find_map(|a| match a {
+                ContainerAttr::With(path) => Some(path),
+                _ => None,
+            })
+        })
+    }
+
+    /// `var` holds a nested `EnumRepr` enum joined into the names of this one
+    pub fn flatten(&self, var: &Variant) -> bool {
+        var.attrs
+            .iter()
+            .any(|a| matches!(a, VariantAttr::Flatten(_)))
+    }
 
-    fn try_from(var: syn::Variant) -> Result<Variant> {
-        split_owned!(var as attrs, ident, fields);
-        let attrs = get_variant_attrs(attrs)?;
+    /// between the name of a `flatten` variant and the nested name, `.` by
+    /// default
+    pub fn separator(&self) -> &str {
+        self.attrs
+            .iter()
+            .find_map(|a| match a {
+                ContainerAttr::Separator(sep) => Some(sep.as_str()),
+                _ => None,
+            })
+            .unwrap_or(".")
+    }
+}
+
+impl Variant {
+    /// `strict` is the `deny_unknown_fields` of the enum
+    pub fn new(var: syn::Variant, strict: bool) -> Result<Variant> {
+        split_owned!(var as attrs, ident, fields, discriminant);
+        let cfgs = take_cfg!(attrs).cloned().collect();
+        let hidden = take!(attrs, "doc").any(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => {
+                list.nested.iter().any(|nested| match nested {
+                    NestedMeta::Meta(Meta::Path(path)) => {
+                        path.is_ident("hidden")
+                    },
+                    _ => false,
+                })
+            },
+            _ => false,
+        });
+        let attrs = get_variant_attrs(attrs, strict)?;
 
         Ok(Variant {
             attrs,
+            cfgs,
+            hidden,
             ident,
             fields,
+            discriminant: discriminant.map(|(_, expr)| expr),
         })
     }
 }
 
-/// ```no_run
+/// ```
 ///
 /// #[macro_use]
 /// extern crate mac_derive;
@@ -131,29 +371,120 @@ impl TryFrom<syn::Variant> for Variant {
 /// make_enum! {
 ///     pub enum EnumIdent: u16 -> VarIdent {
 ///         VarIdent(CONST_NAME = 1),
-///         Another(ANOTHER_CONST = 2)
+///         Another(ANOTHER_CONST = 2),
+///         /// plain variants take an optional discriminant
+///         Plain = 5,
+///         Last
+///     }
+/// }
+///
+/// // repr defaults to `isize`, `-> Variant` is optional and implements
+/// // `Default`
+/// make_enum! {
+///     enum Short {
+///         One,
+///         Two(TWO)
 ///     }
 /// }
 ///
 /// fn main() {
 ///     let ei = EnumIdent::Another as u16;
 ///     assert_eq!(ei, 2);
+///     assert_eq!(Short::Two as isize, 1);
+///     assert_eq!(EnumIdent::default() as u16, 1);
+///
+///     // constant names become associated and raw constants
+///     let _ = EnumIdent::CONST_NAME;
+///     match 2u16 {
+///         enum_ident::ANOTHER_CONST => {},
+///         _ => unreachable!(),
+///     }
+/// }
+/// ```
+///
+/// the raw constants live in a module named after the enum in snake_case,
+/// `enum_ident` above, declared next to the enum: an item of the same name in
+/// that scope collides with it, wrap the enum in `mod name { .. }` to avoid it
+///
+/// extra typed columns become accessor methods
+///
+/// ```
+///
+/// #[macro_use]
+/// extern crate mac_derive;
+///
+/// make_enum! {
+///     pub enum Code: u16 -> Ok columns(desc: &'static str, retry: bool) {
+///         Ok(OK = 0, desc = "ok", retry = false),
+///         Busy(BUSY = 3, desc = "try again later", retry = true),
+///     }
+/// }
+///
+/// fn main() {
+///     assert_eq!(Code::Busy.desc(), "try again later");
+///     assert!(Code::Busy.retry());
+///     assert!(!Code::Ok.retry());
 /// }
 /// ```
+///
+/// variants without a discriminant can be numbered by a strategy, see
+/// [`Numbering`]
+///
+/// ```
+///
+/// #[macro_use]
+/// extern crate mac_derive;
+///
+/// make_enum! {
+///     pub enum Perm: u32 = bits {
+///         None = 0,
+///         Read,
+///         Write,
+///         Exec,
+///     }
+/// }
+///
+/// make_enum! {
+///     pub enum Status: u16 = sequential(start = 100, step = 10) {
+///         Ok,
+///         Moved,
+///     }
+/// }
+///
+/// fn main() {
+///     assert_eq!(Perm::Read as u32, 1);
+///     assert_eq!(Perm::Exec as u32, 4);
+///     assert_eq!(Status::Ok as u16, 100);
+///     assert_eq!(Status::Moved as u16, 110);
+/// }
+/// ```
+///
+/// the generated enum docs end with a `# Values` table listing every variant,
+/// its constant, value in decimal and hex, and string name
+///
+/// variants can also be read from a data file with
+/// `enum Opcode: u8 from "codes/opcodes.csv"`, see [`SourceMacro`]
 #[derive(Debug)]
 pub struct EnumMacro {
     pub attrs: Vec<Attribute>,
     pub vis: Visibility,
-    pub enum_token: token::Enum,
     pub ident: Ident,
-    pub colon_token: token::Colon,
-    pub repr: Ident,
-    pub arrow_token: token::RArrow,
-    pub default: Ident,
-    pub brace_token: token::Brace,
+    pub repr: Option<(token::Colon, Ident)>,
+    pub numbering: Option<(token::Eq, Numbering)>,
+    pub default: Option<(token::RArrow, Ident)>,
+    pub columns: Punctuated<ColumnMacro, token::Comma>,
+    /// `from "file"`, the variants are read from it
+    pub source: Option<SourceMacro>,
     pub variants: Punctuated<VariantMacro, token::Comma>,
 }
 
+/// `name: Type` in `columns(...)`
+#[derive(Debug)]
+pub struct ColumnMacro {
+    pub ident: Ident,
+    pub ty: Type,
+}
+
 #[derive(Debug)]
 pub struct VariantMacro {
     pub attrs: Vec<Attribute>,
@@ -163,10 +494,60 @@ pub struct VariantMacro {
 
 #[derive(Debug)]
 pub struct FieldsMacro {
-    /// wrapping ident and discriminant
-    pub paren_token: token::Paren,
-    pub ident: Ident,
+    /// constant name, given in parentheses with the discriminant
+    pub ident: Option<Ident>,
     pub discriminant: Option<(token::Eq, Expr)>,
+    /// `column = value` pairs following the discriminant
+    pub values: Punctuated<ValueMacro, token::Comma>,
+}
+
+#[derive(Debug)]
+pub struct ValueMacro {
+    pub ident: Ident,
+    pub expr: Expr,
+}
+
+/// types accepted in `make_enum!(enum Ident: repr ...)`
+const REPRS: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+    "i128", "isize", "C",
+];
+
+impl EnumMacro {
+    /// declared repr, `isize` when omitted
+    pub fn repr(&self) -> Ident {
+        match &self.repr {
+            Some((_, repr)) => repr.clone(),
+            None => Ident::new("isize", self.ident.span()),
+        }
+    }
+
+    /// integer type of the raw constants, `isize` for `repr(C)`
+    pub fn raw_repr(&self) -> Ident {
+        match self.repr() {
+            ref repr if repr == "C" => Ident::new("isize", repr.span()),
+            repr => repr,
+        }
+    }
+
+    /// `CONST_NAME`s given to the variants
+    pub fn const_names(&self) -> impl Iterator<Item = &Ident> {
+        self.variants.iter().filter_map(|v| v.fields.ident.as_ref())
+    }
+
+    /// variant declaring the `CONST_NAME`
+    pub fn variant_by_const(&self, name: &Ident) -> Option<&VariantMacro> {
+        self.variants
+            .iter()
+            .find(|v| v.fields.ident.as_ref() == Some(name))
+    }
+
+    /// name of the companion module holding the raw constants, the snake_case
+    /// enum name
+    pub fn consts_mod(&self) -> Ident {
+        let name = RenameRule::Snake.apply_to_variant(&self.ident.to_string());
+        Ident::new(&name, self.ident.span())
+    }
 }
 
 impl Parse for EnumMacro {
@@ -175,25 +556,97 @@ impl Parse for EnumMacro {
 
         let attrs = input.call(Attribute::parse_outer)?;
         let vis = input.parse()?;
-        let enum_token = input.parse()?;
+        input.parse::<token::Enum>()?;
         let ident = input.parse()?;
-        let colon_token = input.parse()?;
-        let repr = input.parse()?;
-        let arrow_token = input.parse()?;
-        let default = input.parse()?;
-        let brace_token = braced!(content in input);
-        let variants = content.parse_terminated(VariantMacro::parse)?;
+
+        let repr = if input.peek(token::Colon) {
+            let colon_token = input.parse()?;
+            let repr = input.parse::<Ident>().map_err(|e| {
+                Error::new(e.span(), "expected repr after `:`, e.g. `u16`")
+            })?;
+
+            if !REPRS.iter().any(|r| repr == r) {
+                return err!(
+                    repr: "unsupported repr `{}`, expected one of: {}",
+                    repr,
+                    REPRS.join(", ")
+                );
+            }
+
+            Some((colon_token, repr))
+        } else {
+            None
+        };
+
+        let numbering = if input.peek(token::Eq) {
+            Some((input.parse()?, input.parse()?))
+        } else {
+            None
+        };
+
+        let default = if input.peek(token::RArrow) {
+            let arrow_token = input.parse()?;
+            let default = input.parse::<Ident>().map_err(|e| {
+                Error::new(e.span(), "expected default variant after `->`")
+            })?;
+
+            Some((arrow_token, default))
+        } else {
+            None
+        };
+
+        let columns = if input.peek(kw::columns) {
+            let list;
+            input.parse::<kw::columns>()?;
+            parenthesized!(list in input);
+            list.parse_terminated(ColumnMacro::parse)?
+        } else {
+            Punctuated::new()
+        };
+
+        let (source, variants) = if input.peek(token::Brace) {
+            braced!(content in input);
+            let variants = content.parse_terminated(VariantMacro::parse)?;
+
+            (None, variants)
+        } else if input.peek(kw::from) {
+            input.parse::<kw::from>()?;
+            let source = SourceMacro {
+                path: input.parse().map_err(|e: Error| {
+                    Error::new(e.span(), "expected file path after `from`")
+                })?,
+            };
+            let variants = source.load(&columns)?;
+
+            (Some(source), variants)
+        } else {
+            return Err(input.error(
+                "expected `: repr`, `= numbering`, `-> DefaultVariant`, \
+                 `columns(...)`, `from \"file\"` or `{ variants }`",
+            ));
+        };
+
+        if let Some((_, default)) = &default {
+            if !variants.iter().any(|v| v.ident == *default) {
+                return err!(
+                    default: "default variant `{}` is not declared in `{}`",
+                    default,
+                    ident
+                );
+            }
+        }
+
+        check_columns(&columns, &variants)?;
 
         Ok(Self {
             attrs,
             vis,
-            enum_token,
             ident,
-            colon_token,
             repr,
-            arrow_token,
+            numbering,
             default,
-            brace_token,
+            columns,
+            source,
             variants,
         })
     }
@@ -211,22 +664,365 @@ impl Parse for VariantMacro {
 
 impl Parse for FieldsMacro {
     fn parse(input: ParseStream) -> Result<Self> {
+        if !input.peek(token::Paren) {
+            return Ok(Self {
+                ident: None,
+                discriminant: parse_discriminant(input)?,
+                values: Punctuated::new(),
+            });
+        }
+
         let content;
         let paren_token = parenthesized!(content in input);
 
+        if content.is_empty() {
+            return Err(Error::new(
+                paren_token.span,
+                "expected constant name, e.g. `Variant(CONST_NAME = 1)`",
+            ));
+        }
+
+        let ident = content.parse::<Ident>().map_err(|e| {
+            Error::new(e.span(), "expected constant name, e.g. `CONST_NAME`")
+        })?;
+        let discriminant = parse_discriminant(&content)?;
+
+        let values = if content.peek(token::Comma) {
+            content.parse::<token::Comma>()?;
+            content.parse_terminated(ValueMacro::parse)?
+        } else {
+            Punctuated::new()
+        };
+
+        if !content.is_empty() {
+            return Err(content.error("expected `= discriminant`, `,` or `)`"));
+        }
+
+        if input.peek(token::Eq) {
+            return Err(input.error(
+                "discriminant must go inside the parentheses, e.g. \
+                 `Variant(CONST_NAME = 1)`",
+            ));
+        }
+
         Ok(Self {
-            paren_token,
-            ident: content.parse()?,
-            discriminant: {
-                if content.peek(token::Eq) {
-                    Some((
-                        content.parse::<token::Eq>()?,
-                        content.parse::<Expr>()?,
-                    ))
-                } else {
-                    None
-                }
-            },
+            ident: Some(ident),
+            discriminant,
+            values,
+        })
+    }
+}
+
+impl Parse for ColumnMacro {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ident = input.parse()?;
+        input.parse::<token::Colon>()?;
+
+        Ok(Self {
+            ident,
+            ty: input.parse()?,
+        })
+    }
+}
+
+impl Parse for ValueMacro {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ident = input.parse::<Ident>()?;
+        input.parse::<token::Eq>().map_err(|e| {
+            Error::new(e.span(), format!("expected `{} = value`", ident))
+        })?;
+
+        Ok(Self {
+            ident,
+            expr: input.parse()?,
+        })
+    }
+}
+
+/// every variant gives exactly one value per declared column
+fn check_columns(
+    columns: &Punctuated<ColumnMacro, token::Comma>,
+    variants: &Punctuated<VariantMacro, token::Comma>,
+) -> Result<()> {
+    let mut declared = HashSet::new();
+    for ColumnMacro { ident, .. } in columns {
+        if !declared.insert(ident) {
+            return err!(ident: "duplicate column `{}`", ident);
+        }
+    }
+
+    let names = columns
+        .iter()
+        .map(|c| c.ident.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    for variant in variants {
+        // `Ok(desc = "ok")` parses as the constant `desc`
+        let name = variant.fields.ident.as_ref();
+        if let Some(name) = name.filter(|name| declared.contains(name)) {
+            return err!(
+                name: "missing constant name for variant `{}`, `{}` is a \
+                       column: write `{}(CONST_NAME, {} = ..)`",
+                variant.ident,
+                name,
+                variant.ident,
+                name
+            );
+        }
+
+        let mut given = HashSet::new();
+        for ValueMacro { ident, .. } in &variant.fields.values {
+            if columns.is_empty() {
+                return err!(
+                    ident: "unknown column `{}`, declare it with `columns({}: Type)`",
+                    ident,
+                    ident
+                );
+            }
+
+            if !declared.contains(ident) {
+                return err!(
+                    ident: "unknown column `{}`, expected one of: {}",
+                    ident,
+                    names
+                );
+            }
+
+            if !given.insert(ident) {
+                return err!(ident: "duplicate value for column `{}`", ident);
+            }
+        }
+
+        if let Some(missing) =
+            columns.iter().find(|c| !given.contains(&c.ident))
+        {
+            let ident = &variant.ident;
+            return err!(
+                ident: "missing column `{}` for variant `{}`",
+                missing.ident,
+                ident
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_discriminant(input: ParseStream) -> Result<Option<(token::Eq, Expr)>> {
+    if input.peek(token::Eq) {
+        let eq_token = input.parse()?;
+        let expr = input.parse::<Expr>().map_err(|e| {
+            Error::new(e.span(), "expected discriminant after `=`")
+        })?;
+
+        Ok(Some((eq_token, expr)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// several related enums in one `make_enum!`, optionally wrapped in a
+/// generated module
+///
+/// constant names must be unique across the block, except between enums
+/// linked by a conversion where a shared constant name pairs the variants
+///
+/// ```
+///
+/// #[macro_use]
+/// extern crate mac_derive;
+///
+/// make_enum! {
+///     pub mod codes {
+///         #[derive(Debug)]
+///         pub enum Request: u16 {
+///             Ping(PING = 1),
+///             Get(GET = 2),
+///         }
+///
+///         #[derive(Debug)]
+///         pub enum Response: u16 {
+///             Pong(PING = 0x8001),
+///             Data(GET = 0x8002),
+///             Error(ERROR = 0x80ff),
+///         }
+///
+///         impl From<Request> for Response;
+///         impl TryFrom<Response> for Request;
+///     }
+/// }
+///
+/// fn main() {
+///     use codes::{Request, Response};
+///     use std::convert::TryFrom;
+///
+///     assert_eq!(Response::from(Request::Ping) as u16, 0x8001);
+///     assert_eq!(Request::try_from(Response::Data).unwrap() as u16, 2);
+///     assert!(Request::try_from(Response::Error).is_err());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct EnumsMacro {
+    pub module: Option<ModMacro>,
+    pub enums: Vec<EnumMacro>,
+    pub convs: Vec<ConvMacro>,
+}
+
+#[derive(Debug)]
+pub struct ModMacro {
+    pub attrs: Vec<Attribute>,
+    pub vis: Visibility,
+    pub ident: Ident,
+}
+
+/// `impl From<Source> for Target;` or `impl TryFrom<Source> for Target;`
+#[derive(Debug)]
+pub struct ConvMacro {
+    pub trait_: Ident,
+    pub source: Ident,
+    pub target: Ident,
+}
+
+impl ConvMacro {
+    pub fn is_try(&self) -> bool {
+        self.trait_ == "TryFrom"
+    }
+
+    fn links(&self, a: &Ident, b: &Ident) -> bool {
+        (self.source == *a && self.target == *b)
+            || (self.source == *b && self.target == *a)
+    }
+}
+
+impl Parse for EnumsMacro {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let fork = input.fork();
+        fork.call(Attribute::parse_outer)?;
+        fork.parse::<Visibility>()?;
+
+        let (module, items) = if fork.peek(token::Mod) {
+            let content;
+            let attrs = input.call(Attribute::parse_outer)?;
+            let vis = input.parse()?;
+            input.parse::<token::Mod>()?;
+            let module = ModMacro {
+                attrs,
+                vis,
+                ident: input.parse()?,
+            };
+            braced!(content in input);
+
+            if !input.is_empty() {
+                return Err(input.error("unexpected tokens after module"));
+            }
+
+            (Some(module), parse_items(&content)?)
+        } else {
+            (None, parse_items(input)?)
+        };
+
+        let (enums, convs) = items;
+        check_enums(&enums, &convs)?;
+
+        Ok(Self {
+            module,
+            enums,
+            convs,
+        })
+    }
+}
+
+impl Parse for ConvMacro {
+    fn parse(input: ParseStream) -> Result<Self> {
+        input.parse::<token::Impl>()?;
+        let trait_ = input.parse::<Ident>()?;
+
+        if trait_ != "From" && trait_ != "TryFrom" {
+            return err!(
+                trait_: "expected `From` or `TryFrom`, found `{}`",
+                trait_
+            );
+        }
+
+        input.parse::<Token![<]>()?;
+        let source = input.parse()?;
+        input.parse::<Token![>]>()?;
+        input.parse::<token::For>()?;
+        let target = input.parse()?;
+        input.parse::<Token![;]>()?;
+
+        Ok(Self {
+            trait_,
+            source,
+            target,
         })
     }
 }
+
+fn parse_items(input: ParseStream) -> Result<(Vec<EnumMacro>, Vec<ConvMacro>)> {
+    let mut enums = Vec::new();
+    let mut convs = Vec::new();
+
+    while !input.is_empty() {
+        if input.peek(token::Impl) {
+            convs.push(input.parse()?);
+        } else {
+            enums.push(input.parse()?);
+
+            // `enum E: u8 from "file"` has no closing brace
+            if input.peek(Token![;]) {
+                input.parse::<Token![;]>()?;
+            }
+        }
+    }
+
+    if enums.is_empty() {
+        return Err(input.error("expected at least one enum"));
+    }
+
+    Ok((enums, convs))
+}
+
+/// unique constant names across the block, unless the enums are linked by a
+/// conversion, and conversions only between declared enums
+fn check_enums(enums: &[EnumMacro], convs: &[ConvMacro]) -> Result<()> {
+    for conv in convs {
+        for ident in &[&conv.source, &conv.target] {
+            if !enums.iter().any(|e| e.ident == **ident) {
+                return err!(ident: "enum `{}` is not declared in this block", ident);
+            }
+        }
+
+        if conv.source == conv.target {
+            let ident = &conv.target;
+            return err!(ident: "cannot convert `{}` into itself", ident);
+        }
+    }
+
+    for (i, e) in enums.iter().enumerate() {
+        for prev in &enums[..i] {
+            if prev.ident == e.ident {
+                let ident = &e.ident;
+                return err!(ident: "enum `{}` is declared twice", ident);
+            }
+
+            if convs.iter().any(|c| c.links(&prev.ident, &e.ident)) {
+                continue;
+            }
+
+            for name in e.const_names() {
+                if prev.const_names().any(|n| n == name) {
+                    return err!(
+                        name: "constant `{}` is already declared in `{}`, link \
+                               the enums with `impl From<..> for ..;` to pair them",
+                        name,
+                        prev.ident
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,289 @@
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    token, Generics, Ident, Member, Path, Result, TraitItem, Type, WhereClause,
+};
+
+mod kw {
+    custom_keyword!(ops);
+}
+
+/// ```
+///
+/// #[macro_use]
+/// extern crate mac_derive;
+///
+/// pub struct UserId(u64);
+///
+/// pub struct Count(u32);
+///
+/// pub struct Name<T> {
+///     inner: T,
+/// }
+///
+/// pub trait Shape {
+///     type Unit;
+///     fn area(&self) -> f64;
+///     fn scale<F: Into<f64>>(&mut self, by: F);
+/// }
+///
+/// pub struct Square(f64);
+///
+/// impl Shape for Square {
+///     type Unit = f64;
+///     fn area(&self) -> f64 { self.0 * self.0 }
+///     fn scale<F: Into<f64>>(&mut self, by: F) { self.0 *= by.into() }
+/// }
+///
+/// pub struct Tile(Square);
+///
+/// forward_impl! {
+///     impl Display, Debug, Hash, FromStr for UserId => 0;
+///     // `: Type` names the field type, needed by `Deref`, `From`, `AsRef`
+///     // without a type argument and the associated items of listed traits;
+///     // `FromStr` then returns the error of the field instead of a boxed one
+///     impl PartialEq, Eq, Deref for Count => 0: u32;
+///     impl<T> Display, AsRef<str> for Name<T> => inner
+///         where T: std::fmt::Display + AsRef<str>;
+///     // traits outside the built-in list spell out the forwarded items
+///     impl Shape {
+///         type Unit;
+///         fn area(&self) -> f64;
+///         fn scale<F: Into<f64>>(&mut self, by: F);
+///     } for Tile => 0: Square
+/// }
+///
+/// fn main() {
+///     let id: UserId = "42".parse().unwrap();
+///     assert_eq!(id.to_string(), "42");
+///     assert!("x".parse::<UserId>().is_err());
+///     assert_eq!(*Count(3), 3);
+///     assert!(Count(3) == Count(3));
+///     assert_eq!(Name { inner: "ann" }.to_string(), "ann");
+///
+///     let mut tile = Tile(Square(2.0));
+///     tile.scale(2);
+///     assert_eq!(tile.area(), 16.0);
+/// }
+/// ```
+///
+/// the built-in traits are `Display`, `Debug`, the other `fmt` traits,
+/// `Hash`, `PartialEq`, `Eq`, `PartialOrd`, `Ord`, `Clone`, `Default`,
+/// `FromStr`, `From`, `AsRef`, `AsMut`, `Borrow`, `BorrowMut`, `Deref` and
+/// `DerefMut`
+///
+/// operators are listed in `ops(..)`, see [`ForwardOps`]
+///
+/// ```
+///
+/// #[macro_use]
+/// extern crate mac_derive;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq)]
+/// pub struct Millis(u64);
+///
+/// forward_impl! {
+///     impl Display, ops(
+///         Add, Sub, Mul<u64>, Div<u64>, AddAssign, SubAssign, Sum,
+///         checked(add, sub), saturating(sub)
+///     ) for Millis => 0
+/// }
+///
+/// fn main() {
+///     let (a, b) = (Millis(10), Millis(3));
+///
+///     assert_eq!(a + b, Millis(13));
+///     assert_eq!(&a - &b, Millis(7));
+///     assert_eq!(a * 2, Millis(20));
+///     assert_eq!(vec![a, b].iter().sum::<Millis>(), Millis(13));
+///     assert_eq!(b.checked_sub(a), None);
+///     assert_eq!(b.saturating_sub(a), Millis(0));
+///
+///     let mut c = a;
+///     c += b;
+///     assert_eq!(c.checked_add(Millis(u64::max_value())), None);
+///     assert_eq!(c.to_string(), "13");
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ForwardMacro {
+    pub impls: Punctuated<ForwardImpl, Token![;]>,
+}
+
+/// `impl<..> Trait, .. for Type => member: Inner where ..`
+#[derive(Debug)]
+pub struct ForwardImpl {
+    pub generics: Generics,
+    pub traits: Punctuated<ForwardItem, Token![,]>,
+    pub ty: Type,
+    pub member: Member,
+    pub inner: Option<(Token![:], Type)>,
+}
+
+#[derive(Debug)]
+pub enum ForwardItem {
+    Trait(ForwardTrait),
+    Ops(ForwardOps),
+}
+
+/// `ops(Add, Mul<u64>, AddAssign, Neg, Sum, checked(add, sub), ..)`
+///
+/// - binary operators `Add`, `Sub`, `Mul`, `Div`, `Rem`, `BitAnd`, `BitOr`,
+///   `BitXor`, `Shl` and `Shr` are implemented for every owned and reference
+///   combination of operands, the right hand side defaults to the newtype
+///   and `Mul<u64>` takes the given type instead
+/// - their `*Assign` forms take an owned or borrowed right hand side
+/// - unary `Neg` and `Not` are implemented for the newtype and a reference
+/// - `Sum` and `Product` fold owned and borrowed items
+/// - `checked(..)`, `saturating(..)` and `wrapping(..)` add inherent
+///   `checked_add` like methods calling the ones of the field: `add`, `sub`,
+///   `mul`, `div` and `neg`, plus `rem` but for `saturating`;
+///   `saturating(neg)` needs a signed field
+#[derive(Debug)]
+pub struct ForwardOps {
+    pub ops: Punctuated<ForwardOp, Token![,]>,
+}
+
+#[derive(Debug)]
+pub enum ForwardOp {
+    /// an operator trait, with its right hand side type
+    Trait(Path),
+    /// `checked(add, sub)`
+    Inherent {
+        kind: Ident,
+        methods: Punctuated<Ident, Token![,]>,
+    },
+}
+
+/// a trait path, with its forwarded items for traits not known to the macro
+#[derive(Debug)]
+pub struct ForwardTrait {
+    pub path: Path,
+    pub brace_token: Option<token::Brace>,
+    pub items: Vec<TraitItem>,
+}
+
+impl ForwardImpl {
+    pub fn inner(&self) -> Option<&Type> {
+        self.inner.as_ref().map(|(_, ty)| ty)
+    }
+}
+
+impl Parse for ForwardMacro {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Self {
+            impls: Punctuated::parse_terminated(input)?,
+        })
+    }
+}
+
+impl Parse for ForwardImpl {
+    fn parse(input: ParseStream) -> Result<Self> {
+        input.parse::<Token![impl]>()?;
+        let mut generics = input.parse::<Generics>()?;
+
+        let mut traits = Punctuated::new();
+        loop {
+            traits.push_value(input.parse()?);
+            if input.peek(Token![for]) {
+                break;
+            }
+            traits.push_punct(input.parse()?);
+        }
+
+        input.parse::<Token![for]>()?;
+        let ty = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let member = input.parse()?;
+        let inner = if input.peek(Token![:]) {
+            Some((input.parse()?, input.parse()?))
+        } else {
+            None
+        };
+        generics.where_clause = input.parse::<Option<WhereClause>>()?;
+
+        Ok(Self {
+            generics,
+            traits,
+            ty,
+            member,
+            inner,
+        })
+    }
+}
+
+impl Parse for ForwardItem {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.peek(kw::ops) && input.peek2(token::Paren) {
+            let content;
+            input.parse::<kw::ops>()?;
+            parenthesized!(content in input);
+            Ok(ForwardItem::Ops(ForwardOps {
+                ops: content.parse_terminated(ForwardOp::parse)?,
+            }))
+        } else {
+            input.parse().map(ForwardItem::Trait)
+        }
+    }
+}
+
+impl Parse for ForwardOp {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let path = input.parse::<Path>()?;
+
+        if !input.peek(token::Paren) {
+            return Ok(ForwardOp::Trait(path));
+        }
+
+        let kind = match path.get_ident() {
+            Some(kind)
+                if kind == "checked"
+                    || kind == "saturating"
+                    || kind == "wrapping" =>
+            {
+                kind.clone()
+            },
+            _ => {
+                return err!(
+                    path: "expected `checked(..)`, `saturating(..)` or \
+                           `wrapping(..)`"
+                )
+            },
+        };
+
+        let content;
+        parenthesized!(content in input);
+        Ok(ForwardOp::Inherent {
+            kind,
+            methods: content.parse_terminated(Ident::parse)?,
+        })
+    }
+}
+
+impl Parse for ForwardTrait {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let path = input.parse()?;
+
+        if !input.peek(token::Brace) {
+            return Ok(Self {
+                path,
+                brace_token: None,
+                items: Vec::new(),
+            });
+        }
+
+        let content;
+        let brace_token = Some(braced!(content in input));
+        let mut items = Vec::new();
+        while !content.is_empty() {
+            items.push(content.parse()?);
+        }
+
+        Ok(Self {
+            path,
+            brace_token,
+            items,
+        })
+    }
+}
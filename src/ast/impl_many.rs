@@ -0,0 +1,186 @@
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    token, Attribute, Generics, Ident, ImplItem, Path, Result, Type,
+    Visibility, WhereClause,
+};
+
+/// ```
+///
+/// #[macro_use]
+/// extern crate mac_derive;
+///
+/// pub trait Color {}
+/// pub trait Marker {}
+///
+/// pub trait Limit {
+///     type Unit;
+///     const MAX: u32;
+///     fn name(&self) -> &'static str { "limit" }
+/// }
+///
+/// pub struct Wrapper<T>(T);
+///
+/// impl_many! {
+///     // marker traits for a list of types
+///     impl Color, Marker for (), u8;
+///     impl<T> Color for Wrapper<T>, Option<T> where T: Copy;
+///
+///     // unit structs created on demand, attributes go to every struct
+///     #[derive(Debug, Clone, Copy, Default)]
+///     impl Color for pub struct Red, Green, Blue;
+///
+///     // shared items, overridden per type
+///     impl Limit {
+///         type Unit = u32;
+///         const MAX: u32 = 10;
+///     } for Red, Green {
+///         const MAX: u32 = 20;
+///         fn name(&self) -> &'static str { "green" }
+///     }, Blue {
+///         type Unit = u8;
+///     }
+/// }
+///
+/// fn main() {
+///     assert_eq!(<Red as Limit>::MAX, 10);
+///     assert_eq!(<Green as Limit>::MAX, 20);
+///     assert_eq!(Green.name(), "green");
+///     assert_eq!(Red.name(), "limit");
+///     let _: <Blue as Limit>::Unit = 0u8;
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ImplManyMacro {
+    pub impls: Punctuated<ImplMany, Token![;]>,
+}
+
+/// `#[..] impl<..> Trait, .. { items } for vis struct Type { items }, ..
+/// where ..`
+#[derive(Debug)]
+pub struct ImplMany {
+    /// copied to the generated structs, or to every impl without `struct`
+    pub attrs: Vec<Attribute>,
+    pub generics: Generics,
+    pub traits: Punctuated<Path, Token![,]>,
+    /// items shared by every type
+    pub brace_token: Option<token::Brace>,
+    pub items: Vec<ImplItem>,
+    pub vis: Visibility,
+    pub struct_token: Option<Token![struct]>,
+    pub types: Punctuated<ImplManyType, Token![,]>,
+}
+
+/// a type with the items overriding the shared ones
+#[derive(Debug)]
+pub struct ImplManyType {
+    pub ty: Type,
+    pub brace_token: Option<token::Brace>,
+    pub items: Vec<ImplItem>,
+}
+
+impl ImplMany {
+    /// shared items, replaced by the `ty` ones of the same name
+    pub fn items_for<'a>(&'a self, ty: &'a ImplManyType) -> Vec<&'a ImplItem> {
+        self.items
+            .iter()
+            .filter(|item| {
+                let name = impl_item_ident(item);
+                name.is_none()
+                    || ty.items.iter().all(|i| impl_item_ident(i) != name)
+            })
+            .chain(&ty.items)
+            .collect()
+    }
+}
+
+/// name of an associated item, `None` for macros and verbatim tokens
+pub fn impl_item_ident(item: &ImplItem) -> Option<&Ident> {
+    match item {
+        ImplItem::Const(c) => Some(&c.ident),
+        ImplItem::Method(m) => Some(&m.sig.ident),
+        ImplItem::Type(t) => Some(&t.ident),
+        _ => None,
+    }
+}
+
+fn parse_impl_items(
+    input: ParseStream,
+) -> Result<(Option<token::Brace>, Vec<ImplItem>)> {
+    if !input.peek(token::Brace) {
+        return Ok((None, Vec::new()));
+    }
+
+    let content;
+    let brace_token = braced!(content in input);
+    let mut items = Vec::new();
+    while !content.is_empty() {
+        items.push(content.parse()?);
+    }
+
+    Ok((Some(brace_token), items))
+}
+
+impl Parse for ImplManyMacro {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Self {
+            impls: Punctuated::parse_terminated(input)?,
+        })
+    }
+}
+
+impl Parse for ImplMany {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        input.parse::<Token![impl]>()?;
+        let mut generics = input.parse::<Generics>()?;
+
+        let mut traits = Punctuated::new();
+        loop {
+            traits.push_value(input.parse()?);
+            if input.peek(Token![for]) || input.peek(token::Brace) {
+                break;
+            }
+            traits.push_punct(input.parse()?);
+        }
+
+        let (brace_token, items) = parse_impl_items(input)?;
+        input.parse::<Token![for]>()?;
+        let vis = input.parse()?;
+        let struct_token = input.parse()?;
+
+        let mut types = Punctuated::new();
+        loop {
+            types.push_value(input.parse()?);
+            if !input.peek(Token![,]) {
+                break;
+            }
+            types.push_punct(input.parse()?);
+        }
+        generics.where_clause = input.parse::<Option<WhereClause>>()?;
+
+        Ok(Self {
+            attrs,
+            generics,
+            traits,
+            brace_token,
+            items,
+            vis,
+            struct_token,
+            types,
+        })
+    }
+}
+
+impl Parse for ImplManyType {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ty = input.parse()?;
+        let (brace_token, items) = parse_impl_items(input)?;
+
+        Ok(Self {
+            ty,
+            brace_token,
+            items,
+        })
+    }
+}
@@ -1,7 +1,19 @@
+mod accessors;
 mod attr;
 mod case;
 mod enum_repr;
+mod forward;
+mod impl_many;
+mod newtype;
+mod numbering;
 mod parser;
+mod preset;
 mod symbol;
+mod table;
+mod tagged;
 
-pub use self::{attr::*, case::*, enum_repr::*, parser::*, symbol::*};
+pub use self::{
+    accessors::*, attr::*, case::*, enum_repr::*, forward::*, impl_many::*,
+    newtype::*, numbering::*, parser::*, preset::*, symbol::*, table::*,
+    tagged::*,
+};
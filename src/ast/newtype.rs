@@ -0,0 +1,189 @@
+use ast::{get_container_attrs, ContainerAttr, NewtypeAttr};
+use std::collections::HashSet;
+use syn::{
+    parse::{Parse, ParseStream},
+    Data, DeriveInput, Generics, Ident, Member, Path, Result, Type,
+};
+
+/// ```
+///
+/// use mac_derive::Newtype;
+/// use std::convert::TryFrom;
+///
+/// // conversions, `AsRef` and `Borrow` of the inner value, `transparent`
+/// // formats and parses as the inner value
+/// #[derive(Newtype, Debug, PartialEq)]
+/// #[enum_repr(transparent, deref)]
+/// pub struct UserId(u64);
+///
+/// fn check_port(port: &u16) -> Result<(), String> {
+///     if *port >= 1024 {
+///         Ok(())
+///     } else {
+///         Err(format!("port {} is reserved", port))
+///     }
+/// }
+///
+/// // a validator makes construction fallible, its error is a `String`
+/// // unless `error = "Type"` is given
+/// #[derive(Newtype, Debug)]
+/// #[enum_repr(transparent, validate = "check_port")]
+/// pub struct Port {
+///     port: u16,
+/// }
+///
+/// #[derive(Debug, PartialEq)]
+/// pub struct NameError(String);
+///
+/// // `FromStr` passes a failed inner parse to the error as a `String`
+/// impl From<String> for NameError {
+///     fn from(e: String) -> Self {
+///         NameError(e)
+///     }
+/// }
+///
+/// fn check_name(name: &String) -> Result<(), NameError> {
+///     match name.is_empty() {
+///         true => Err(NameError("empty name".to_owned())),
+///         false => Ok(()),
+///     }
+/// }
+///
+/// #[derive(Newtype, Debug)]
+/// #[enum_repr(transparent, validate = "check_name", error = "NameError")]
+/// pub struct Name(String);
+///
+/// fn main() {
+///     let id = UserId::from(7);
+///     assert_eq!(*id + 1, 8);
+///     assert_eq!("7".parse::<UserId>().unwrap(), id);
+///     assert_eq!(id.to_string(), "7");
+///     assert_eq!(u64::from(id), 7);
+///
+///     assert!(Port::new(80).is_err());
+///     assert!(Port::try_from(8080).is_ok());
+///     assert!("80".parse::<Port>().is_err());
+///     let empty = NameError("empty name".to_owned());
+///     assert_eq!("".parse::<Name>().unwrap_err(), empty);
+///     assert_eq!("bob".parse::<Name>().unwrap().to_string(), "bob");
+/// }
+/// ```
+///
+/// with `transparent` and `validate`, `FromStr` turns the error of the inner
+/// parse into a `String` and then into the `error` type, which therefore
+/// needs `From<String>`
+///
+/// other fields are allowed when they are `PhantomData` markers
+#[derive(Debug)]
+pub struct Newtype {
+    pub attrs: HashSet<ContainerAttr>,
+    pub ident: Ident,
+    pub generics: Generics,
+    pub member: Member,
+    pub ty: Type,
+    /// `PhantomData` fields, filled in on construction
+    pub markers: Vec<Member>,
+}
+
+impl Parse for Newtype {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let derive_input: DeriveInput = input.parse()?;
+        split_owned!(derive_input as attrs, ident, generics, data);
+
+        let fields = match data {
+            Data::Struct(data) => data.fields,
+            _ => return err!(ident: "only work for struct"),
+        };
+
+        let mut inner = None;
+        let mut markers = Vec::new();
+        for (i, field) in fields.into_iter().enumerate() {
+            let member = match field.ident {
+                Some(ident) => Member::Named(ident),
+                None => Member::Unnamed(i.into()),
+            };
+
+            if is_phantom(&field.ty) {
+                markers.push(member);
+            } else if inner.is_none() {
+                inner = Some((member, field.ty));
+            } else {
+                return err!(
+                    ident: "`{}` has more than one non `PhantomData` field",
+                    ident
+                );
+            }
+        }
+
+        let (member, ty) = match inner {
+            Some(inner) => inner,
+            None => return err!(ident: "`{}` has no field to wrap", ident),
+        };
+
+        Ok(Self {
+            attrs: get_container_attrs::<NewtypeAttr>(attrs)?
+                .into_iter()
+                .map(|NewtypeAttr(a)| a)
+                .collect(),
+            ident,
+            generics,
+            member,
+            ty,
+            markers,
+        })
+    }
+}
+
+impl Newtype {
+    pub fn transparent(&self) -> bool {
+        self.attrs
+            .iter()
+            .any(|a| matches!(a, ContainerAttr::Trans(_)))
+    }
+
+    pub fn deref(&self) -> bool {
+        self.attrs
+            .iter()
+            .any(|a| matches!(a, ContainerAttr::Deref(_)))
+    }
+
+    pub fn validate(&self) -> Option<&Path> {
+        self.attrs.iter().find_map(|a| match a {
+            ContainerAttr::Validate(path) => Some(path),
+            _ => None,
+        })
+    }
+
+    /// error of the validator, `String` by default
+    pub fn error(&self) -> Type {
+        self.attrs
+            .iter()
+            .find_map(|a| match a {
+                ContainerAttr::Error(ty) => Some((**ty).clone()),
+                _ => None,
+            })
+            .unwrap_or_else(|| parse_quote!(::std::string::String))
+    }
+
+    /// the inner type is one of the struct type parameters, `From<Self>`
+    /// for it would not be allowed
+    pub fn inner_is_param(&self) -> bool {
+        self.generics.type_params().any(|param| match &self.ty {
+            Type::Path(ty) => {
+                ty.qself.is_none() && ty.path.is_ident(&param.ident)
+            },
+            _ => false,
+        })
+    }
+}
+
+fn is_phantom(ty: &Type) -> bool {
+    match ty {
+        Type::Path(ty) => ty
+            .path
+            .segments
+            .last()
+            .is_some_and(|s| s.ident == "PhantomData"),
+        _ => false,
+    }
+}
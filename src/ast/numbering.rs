@@ -0,0 +1,245 @@
+use ast::{EnumMacro, VariantMacro};
+use syn::{
+    parse::{Parse, ParseStream},
+    token, Expr, ExprLit, ExprUnary, Ident, Lit, LitInt, Result, UnOp,
+};
+
+/// `enum Ident: repr = <numbering>` strategy for variants without an explicit
+/// discriminant
+///
+/// - `bits`: `1, 2, 4, ...`
+/// - `sequential(start = 100, step = 10)`: both arguments are optional and
+///   default to `0` and `1`
+/// - `hashed`: FNV-1a hash of the variant name, truncated to the repr
+///
+/// the width of `usize`, `isize` and `C` depends on the target, the values
+/// generated for them are kept to 32 bits so they fit every target
+#[derive(Debug)]
+pub enum Numbering {
+    Bits,
+    Sequential { start: i128, step: i128 },
+    Hashed,
+}
+
+mod kw {
+    custom_keyword!(start);
+    custom_keyword!(step);
+}
+
+impl Numbering {
+    /// value of the `nth` variant numbered by this strategy
+    fn value(
+        &self,
+        nth: usize,
+        variant: &VariantMacro,
+        (bits, signed): (u32, bool),
+    ) -> Result<i128> {
+        let ident = &variant.ident;
+
+        match self {
+            Numbering::Bits => {
+                let max = if signed { bits - 1 } else { bits };
+                if nth as u32 >= max || nth >= 127 {
+                    return err!(
+                        ident: "`bits` numbering of `{}` overflows the repr, \
+                                at most {} variants fit",
+                        ident,
+                        max
+                    );
+                }
+
+                Ok(1 << nth)
+            },
+            Numbering::Sequential { start, step } => (nth as i128)
+                .checked_mul(*step)
+                .and_then(|n| n.checked_add(*start))
+                .map(Ok)
+                .unwrap_or_else(|| {
+                    err!(ident: "`sequential` numbering of `{}` overflows", ident)
+                }),
+            Numbering::Hashed => {
+                let hash = ident
+                    .to_string()
+                    .bytes()
+                    .fold(0xcbf2_9ce4_8422_2325_u64, |hash, b| {
+                        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+                    });
+
+                let keep = if signed { bits - 1 } else { bits }.min(64);
+                Ok(match keep {
+                    64 => i128::from(hash),
+                    keep => i128::from(hash & ((1 << keep) - 1)),
+                })
+            },
+        }
+    }
+}
+
+impl Parse for Numbering {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ident = input.parse::<Ident>()?;
+
+        if ident == "bits" {
+            return Ok(Numbering::Bits);
+        }
+
+        if ident == "hashed" {
+            return Ok(Numbering::Hashed);
+        }
+
+        if ident != "sequential" {
+            return err!(
+                ident: "unknown numbering `{}`, expected `bits`, \
+                        `sequential(start = .., step = ..)` or `hashed`",
+                ident
+            );
+        }
+
+        let (mut start, mut step) = (0, 1);
+
+        if input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+
+            while !content.is_empty() {
+                let lookahead = content.lookahead1();
+                if lookahead.peek(kw::start) {
+                    content.parse::<kw::start>()?;
+                    content.parse::<token::Eq>()?;
+                    start = parse_int(&content)?;
+                } else if lookahead.peek(kw::step) {
+                    content.parse::<kw::step>()?;
+                    content.parse::<token::Eq>()?;
+                    step = parse_int(&content)?;
+                } else {
+                    return Err(lookahead.error());
+                }
+
+                if !content.is_empty() {
+                    content.parse::<token::Comma>()?;
+                }
+            }
+        }
+
+        if step == 0 {
+            return err!(ident: "`sequential` numbering needs a non zero step");
+        }
+
+        Ok(Numbering::Sequential { start, step })
+    }
+}
+
+fn parse_int(input: ParseStream) -> Result<i128> {
+    let neg = input.parse::<Option<Token![-]>>()?.is_some();
+    let value = input.parse::<LitInt>()?.base10_parse::<i128>()?;
+
+    Ok(if neg { -value } else { value })
+}
+
+/// value of a literal discriminant, `None` for any other expression
+pub fn int_from_expr(expr: &Expr) -> Option<i128> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => lit.base10_parse().ok(),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => int_from_expr(expr).map(|v: i128| -v),
+        Expr::Group(group) => int_from_expr(&group.expr),
+        Expr::Paren(paren) => int_from_expr(&paren.expr),
+        _ => None,
+    }
+}
+
+/// bit width and signedness of a raw repr, see [`EnumMacro::raw_repr`]
+///
+/// `usize` and `isize` are taken as 64 bits wide, rustc checks the
+/// discriminants against the actual target width
+pub fn repr_bits(repr: &Ident) -> (u32, bool) {
+    let repr = repr.to_string();
+    match repr.as_str() {
+        "usize" => (64, false),
+        "isize" => (64, true),
+        _ => (repr[1..].parse().unwrap_or(64), repr.starts_with('i')),
+    }
+}
+
+/// smallest and largest value of an integer type
+fn range(bits: u32, signed: bool) -> (i128, i128) {
+    match (bits, signed) {
+        (128, false) => (0, i128::MAX),
+        (128, true) => (i128::MIN, i128::MAX),
+        (bits, false) => (0, (1 << bits) - 1),
+        (bits, true) => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+    }
+}
+
+/// whether the width of a raw repr depends on the target
+fn is_pointer_sized(repr: &Ident) -> bool {
+    repr == "usize" || repr == "isize"
+}
+
+impl EnumMacro {
+    /// numeric value of every variant when it can be known at expansion time,
+    /// and whether it was generated by the numbering strategy
+    ///
+    /// collisions and values not fitting the repr are reported here
+    pub fn values(&self) -> Result<Vec<(Option<i128>, bool)>> {
+        let repr = self.raw_repr();
+        let (bits, signed) = repr_bits(&repr);
+        let numbered = if is_pointer_sized(&repr) { 32 } else { bits };
+
+        let mut values: Vec<(Option<i128>, bool)> = Vec::new();
+        let mut nth = 0;
+
+        for variant in &self.variants {
+            let value = match (&variant.fields.discriminant, &self.numbering) {
+                (Some((_, expr)), _) => (int_from_expr(expr), false),
+                (None, Some((_, numbering))) => {
+                    let value =
+                        numbering.value(nth, variant, (numbered, signed))?;
+                    nth += 1;
+                    (Some(value), true)
+                },
+                (None, None) => match values.last() {
+                    Some((prev, _)) => (prev.map(|v| v + 1), false),
+                    None => (Some(0), false),
+                },
+            };
+
+            if let (Some(v), ident) = (value.0, &variant.ident) {
+                let (min, max) =
+                    range(if value.1 { numbered } else { bits }, signed);
+                if v < min || v > max {
+                    return err!(
+                        ident: "value {} of `{}` does not fit in `{}`{}",
+                        v,
+                        ident,
+                        repr,
+                        if value.1 && numbered != bits {
+                            " on 32 bit targets"
+                        } else {
+                            ""
+                        }
+                    );
+                }
+
+                let prev = values.iter().position(|(p, _)| *p == Some(v));
+                if let Some(prev) = prev {
+                    return err!(
+                        ident: "value {} of `{}` collides with `{}`",
+                        v,
+                        ident,
+                        self.variants[prev].ident
+                    );
+                }
+            }
+
+            values.push(value);
+        }
+
+        Ok(values)
+    }
+}
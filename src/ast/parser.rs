@@ -1,15 +1,15 @@
 #![allow(dead_code)]
 
-use ast::{AttributesBucket, ContainerAttr, VariantAttr, ID};
+use ast::{suggest, AttributesBucket, DENY_UNKNOWN_FIELDS, ID};
 use proc_macro2::{Group, Literal, Span, TokenStream, TokenTree};
 use quote::ToTokens;
-use std::{collections::HashSet, convert::TryFrom, hash::Hash};
+use std::collections::HashSet;
 use syn::{
-    parse::Parse, spanned::Spanned, Attribute, Error, Lit, LitStr, Meta,
-    MetaList, MetaNameValue, NestedMeta, Path, Result, Type,
+    parse::Parse, spanned::Spanned, Attribute, Error, Ident, Lit, LitStr, Meta,
+    NestedMeta, Path, Result, Type,
 };
 
-fn parse_lit_str_into_ty(s: &LitStr) -> Result<Type> {
+pub fn parse_lit_str_into_ty(s: &LitStr) -> Result<Type> {
     token_from_str(s.value()).map_err(|e| {
         Error::new(
             s.span(),
@@ -22,7 +22,7 @@ fn parse_lit_str_into_ty(s: &LitStr) -> Result<Type> {
     })
 }
 
-fn parse_lit_into_path(s: &LitStr) -> Result<Path> {
+pub fn parse_lit_into_path(s: &LitStr) -> Result<Path> {
     token_from_str(s.value()).map_err(|e| {
         Error::new(
             s.span(),
@@ -126,7 +126,7 @@ pub fn get_meta_items(attr: &Attribute, set: &mut HashSet<Meta>) -> Result<()> {
 pub fn get_all_attrs2(attrs: Vec<Attribute>) -> Result<Vec<Meta>> {
     Ok(attrs
         .iter()
-        .flat_map(|attr| get_meta_items2(attr))
+        .flat_map(get_meta_items2)
         .flatten()
         .collect())
 }
@@ -139,29 +139,72 @@ pub fn get_all_attrs(attrs: &[Attribute]) -> Result<HashSet<Meta>> {
     Ok(set)
 }
 
+/// `deny_unknown_fields = false` among the container attributes turns off
+/// strict parsing, which rejects unknown keys
+pub fn deny_unknown_fields(attrs: &[Attribute]) -> Result<bool> {
+    let mut deny = true;
+    for attr in attrs.iter().filter(|attr| attr.path == ID) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list.nested,
+            _ => continue,
+        };
+
+        for nested in list {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(m))
+                    if m.path == DENY_UNKNOWN_FIELDS =>
+                {
+                    deny = match m.lit {
+                        Lit::Bool(b) => b.value,
+                        lit => {
+                            return err!(lit: "expected deny_unknown_fields = bool")
+                        },
+                    };
+                },
+                NestedMeta::Meta(Meta::Path(m)) if m == DENY_UNKNOWN_FIELDS => {
+                    deny = true;
+                },
+                _ => {},
+            }
+        }
+    }
+
+    Ok(deny)
+}
+
 pub fn get_container_attrs<A>(attrs: Vec<Attribute>) -> Result<HashSet<A>>
 where
     A: AttributesBucket,
 {
+    let strict = deny_unknown_fields(&attrs)?;
     let mut set = HashSet::new();
     for attr in attrs {
-        get_attr(attr.parse_meta()?, &mut set)?;
+        get_attr(attr.parse_meta()?, &mut set, strict, true)?;
     }
     Ok(set)
 }
 
-pub fn get_variant_attrs<A>(attrs: Vec<Attribute>) -> Result<HashSet<A>>
+/// `strict` is the `deny_unknown_fields` of the container
+pub fn get_variant_attrs<A>(
+    attrs: Vec<Attribute>,
+    strict: bool,
+) -> Result<HashSet<A>>
 where
     A: AttributesBucket,
 {
     let mut set = HashSet::new();
     for attr in attrs {
-        get_attr(attr.parse_meta()?, &mut set)?;
+        get_attr(attr.parse_meta()?, &mut set, strict, false)?;
     }
     Ok(set)
 }
 
-pub fn get_attr<A>(meta: Meta, set: &mut HashSet<A>) -> Result<()>
+pub fn get_attr<A>(
+    meta: Meta,
+    set: &mut HashSet<A>,
+    strict: bool,
+    container: bool,
+) -> Result<()>
 where
     A: AttributesBucket,
 {
@@ -172,6 +215,25 @@ where
     match meta {
         Meta::List(list) => {
             for nest in list.nested {
+                let path = match &nest {
+                    NestedMeta::Meta(m) => m.path(),
+                    m => {
+                        return err!(m: "unexpected attribute {}!", m.to_token_stream())
+                    },
+                };
+
+                // read up front by `deny_unknown_fields`
+                if container && path == DENY_UNKNOWN_FIELDS {
+                    continue;
+                }
+
+                if !A::KEYS.iter().any(|key| path == key) {
+                    if strict && !A::SHARED.iter().any(|key| path == key) {
+                        return Err(unknown_key::<A>(path, container));
+                    }
+                    continue;
+                }
+
                 match nest {
                     NestedMeta::Meta(Meta::NameValue(m)) => {
                         let m = A::try_from(m)?;
@@ -181,9 +243,11 @@ where
                         let m = A::try_from(m)?;
                         set.insert(m);
                     },
-                    m => {
-                        return err!(m: "unexpected attribute {}!", m.to_token_stream())
+                    NestedMeta::Meta(Meta::Path(m)) => {
+                        let m = A::try_from(m)?;
+                        set.insert(m);
                     },
+                    NestedMeta::Lit(_) => unreachable!(),
                 }
             }
 
@@ -199,3 +263,57 @@ where
         ),
     }
 }
+
+/// unknown `path` with the closest key and every key of the position
+pub fn unknown_key<A>(path: &Path, container: bool) -> Error
+where
+    A: AttributesBucket,
+{
+    let mut keys = A::KEYS.to_vec();
+    if container {
+        keys.push(DENY_UNKNOWN_FIELDS);
+    }
+
+    let name = path.to_token_stream().to_string();
+    let hint = match suggest(&name, &keys) {
+        Some(key) => format!(", did you mean `{}`?", key),
+        None => String::new(),
+    };
+    let keys = keys
+        .iter()
+        .map(|key| format!("`{}`", key))
+        .collect::<Vec<_>>();
+
+    Error::new(
+        path.span(),
+        format!(
+            "unknown {} attribute `{}`{}\nexpected one of {}",
+            A::POSITION,
+            name,
+            hint,
+            keys.join(", ")
+        ),
+    )
+}
+
+/// integer of `#[repr(..)]`, skipping `C` and `align(..)`
+pub fn repr_int(attrs: &[Attribute]) -> Option<Ident> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("repr"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .find_map(|nested| match nested {
+            NestedMeta::Meta(Meta::Path(path)) => path
+                .get_ident()
+                .filter(|ident| {
+                    let ident = ident.to_string();
+                    ident.starts_with('u') || ident.starts_with('i')
+                })
+                .cloned(),
+            _ => None,
+        })
+}
@@ -0,0 +1,363 @@
+use proc_macro2::Span;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+use syn::{
+    parse::{Parse, ParseStream},
+    Attribute, Error, Ident, Result,
+};
+
+/// file holding the project presets, looked up from `CARGO_MANIFEST_DIR`
+/// upwards so a whole workspace can share one
+pub const PRESETS_FILE: &str = "awe.presets";
+
+/// overrides the lookup with a path relative to `CARGO_MANIFEST_DIR`
+pub const PRESETS_ENV: &str = "MAC_DERIVE_PRESETS";
+
+/// a named bundle of attributes for `#[awe(name)]`
+///
+/// ```text
+/// // awe.presets
+/// preset wire_enum {
+///     #[repr(u16)]
+///     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumRepr)]
+///     #[enum_repr(rename_all = "snake_case")]
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Preset {
+    pub ident: Ident,
+    pub attrs: Vec<Attribute>,
+}
+
+#[derive(Debug)]
+pub struct Presets {
+    /// file the presets were read from
+    pub path: PathBuf,
+    pub presets: Vec<Preset>,
+}
+
+mod kw {
+    custom_keyword!(preset);
+}
+
+impl Parse for Preset {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+
+        input.parse::<kw::preset>()?;
+        let ident = input.parse()?;
+        braced!(content in input);
+
+        Ok(Self {
+            ident,
+            attrs: content.call(Attribute::parse_outer)?,
+        })
+    }
+}
+
+impl Presets {
+    /// read the presets file, errors point at `span`
+    pub fn load(span: Span) -> Result<Self> {
+        let path = find_presets_file().map_err(|e| Error::new(span, e))?;
+
+        let text = fs::read_to_string(&path).map_err(|e| {
+            Error::new(
+                span,
+                format!("failed to read `{}`: {}", path.display(), e),
+            )
+        })?;
+
+        let mut presets = Vec::new();
+        let mut input = text.as_str();
+
+        // parse one preset at a time to report the failing line
+        while !input.trim().is_empty() {
+            let start = text.len() - input.trim_start().len();
+            let line = text[..start].matches('\n').count() + 1;
+            let error = |e: &dyn std::fmt::Display| {
+                Error::new(span, format!("{}:{}: {}", path.display(), line, e))
+            };
+
+            let end = preset_end(input).map_err(|e| error(&e))?;
+            let preset = syn::parse_str::<Preset>(&input[..end])
+                .map_err(|e| error(&e))?;
+
+            presets.push(preset);
+            input = &input[end..];
+        }
+
+        Ok(Self { path, presets })
+    }
+
+    pub fn get(&self, ident: &Ident) -> Option<&Preset> {
+        self.presets.iter().find(|p| p.ident == *ident)
+    }
+
+    pub fn names(&self) -> String {
+        self.presets
+            .iter()
+            .map(|p| p.ident.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// `MAC_DERIVE_PRESETS`, else the first `awe.presets` from
+/// `CARGO_MANIFEST_DIR` upwards
+fn find_presets_file() -> std::result::Result<PathBuf, String> {
+    let root = env::var("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .map_err(|_| "`CARGO_MANIFEST_DIR` is not set".to_owned())?;
+
+    if let Ok(path) = env::var(PRESETS_ENV) {
+        return Ok(root.join(path));
+    }
+
+    let mut dir: Option<&Path> = Some(&root);
+    while let Some(d) = dir {
+        let path = d.join(PRESETS_FILE);
+        if path.is_file() {
+            return Ok(path);
+        }
+        dir = d.parent();
+    }
+
+    Err(format!(
+        "no `{}` found in `{}` or its parents, add one or set `{}` to a \
+         path relative to `CARGO_MANIFEST_DIR`",
+        PRESETS_FILE,
+        root.display(),
+        PRESETS_ENV
+    ))
+}
+
+/// byte offset just past the closing brace of the first preset
+///
+/// delimiters are checked here since the tokenizer aborts compilation on
+/// unbalanced input instead of returning an error; comments, string, raw
+/// string and char literals are skipped
+fn preset_end(input: &str) -> std::result::Result<usize, String> {
+    let bytes = input.as_bytes();
+    let mut stack = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let ch = bytes[i];
+        match ch {
+            b'/' if bytes[i..].starts_with(b"//") => {
+                i = bytes[i..]
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(bytes.len(), |n| i + n);
+            },
+            b'/' if bytes[i..].starts_with(b"/*") => {
+                i = block_comment_end(bytes, i)?;
+            },
+            b'"' => i = string_end(bytes, i + 1, 0)?,
+            b'r' if is_literal_start(bytes, i) => {
+                let hashes =
+                    bytes[i + 1..].iter().take_while(|&&b| b == b'#').count();
+                if bytes.get(i + 1 + hashes) == Some(&b'"') {
+                    i = string_end(bytes, i + 2 + hashes, hashes)?;
+                }
+            },
+            b'\'' => {
+                // `'a` lifetimes and labels are left alone
+                let len = match bytes.get(i + 1) {
+                    Some(b'\\') => {
+                        let end = bytes
+                            .get(i + 3..)
+                            .and_then(|rest| {
+                                rest.iter().position(|&b| b == b'\'')
+                            })
+                            .ok_or("unterminated char literal")?;
+                        Some(end + 2)
+                    },
+                    Some(_) => input[i + 1..]
+                        .chars()
+                        .next()
+                        .map(char::len_utf8)
+                        .filter(|len| bytes.get(i + 1 + len) == Some(&b'\'')),
+                    None => None,
+                };
+
+                if let Some(len) = len {
+                    i += len + 1;
+                }
+            },
+            b'(' | b'[' | b'{' => stack.push(ch),
+            b')' | b']' | b'}' => {
+                let open = match ch {
+                    b')' => b'(',
+                    b']' => b'[',
+                    _ => b'{',
+                };
+
+                if stack.pop() != Some(open) {
+                    return Err(format!("unbalanced `{}`", ch as char));
+                }
+
+                if ch == b'}' && stack.is_empty() {
+                    return Ok(i + 1);
+                }
+            },
+            _ => {},
+        }
+
+        i += 1;
+    }
+
+    match stack.last() {
+        Some(&open) => Err(format!("unclosed `{}`", open as char)),
+        None => Ok(input.len()),
+    }
+}
+
+/// `r` starting a raw string, not ending an identifier; `br` included
+fn is_literal_start(bytes: &[u8], i: usize) -> bool {
+    let ident = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_';
+    match i.checked_sub(1).map(|p| (p, &bytes[p])) {
+        None => true,
+        Some((p, b'b')) => p.checked_sub(1).is_none_or(|p| !ident(&bytes[p])),
+        Some((_, b)) => !ident(b),
+    }
+}
+
+/// offset of the quote, or last `#`, closing a string whose content starts at
+/// `i`; raw strings have `hashes` and no escapes
+fn string_end(
+    bytes: &[u8],
+    mut i: usize,
+    hashes: usize,
+) -> std::result::Result<usize, String> {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if hashes == 0 => i += 1,
+            b'"' if bytes[i + 1..]
+                .iter()
+                .take(hashes)
+                .filter(|&&b| b == b'#')
+                .count()
+                == hashes =>
+            {
+                return Ok(i + hashes);
+            },
+            _ => {},
+        }
+        i += 1;
+    }
+
+    Err("unterminated string".into())
+}
+
+/// offset of the `/` closing the `/*` comment at `i`, comments nest
+fn block_comment_end(
+    bytes: &[u8],
+    mut i: usize,
+) -> std::result::Result<usize, String> {
+    let mut depth = 0;
+
+    while i + 1 < bytes.len() {
+        match &bytes[i..i + 2] {
+            b"/*" => {
+                depth += 1;
+                i += 1;
+            },
+            b"*/" => {
+                depth -= 1;
+                i += 1;
+                if depth == 0 {
+                    return Ok(i);
+                }
+            },
+            _ => {},
+        }
+        i += 1;
+    }
+
+    Err("unterminated block comment".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::preset_end;
+
+    fn first(input: &str) -> &str {
+        &input[..preset_end(input).unwrap()]
+    }
+
+    #[test]
+    fn ends_at_closing_brace() {
+        let input = "preset a {\n    #[derive(Debug)]\n}\npreset b {}";
+        assert_eq!(first(input), "preset a {\n    #[derive(Debug)]\n}");
+        assert_eq!(first("  \n"), "  \n");
+    }
+
+    #[test]
+    fn skips_comments() {
+        assert_eq!(first("preset a { // }\n} x"), "preset a { // }\n}");
+        assert_eq!(
+            first("preset a { /* } /* } */ } */ } x"),
+            "preset a { /* } /* } */ } */ }"
+        );
+    }
+
+    #[test]
+    fn unterminated() {
+        assert_eq!(
+            preset_end("preset a { '\\"),
+            Err("unterminated char literal".into())
+        );
+        assert_eq!(
+            preset_end("preset a { '\\n"),
+            Err("unterminated char literal".into())
+        );
+        assert_eq!(
+            preset_end("preset a { \"}"),
+            Err("unterminated string".into())
+        );
+        assert_eq!(
+            preset_end("preset a { /* }"),
+            Err("unterminated block comment".into())
+        );
+    }
+
+    #[test]
+    fn skips_literals() {
+        assert_eq!(
+            first(r#"preset a { #[doc = "}\"}"] } x"#),
+            r#"preset a { #[doc = "}\"}"] }"#
+        );
+        assert_eq!(
+            first(r###"preset a { #[doc = r#"}"}"#] } x"###),
+            r###"preset a { #[doc = r#"}"}"#] }"###
+        );
+        assert_eq!(
+            first(r#"preset a { #[x(br"}", '{', '\'', 'é')] } x"#),
+            r#"preset a { #[x(br"}", '{', '\'', 'é')] }"#
+        );
+        assert_eq!(
+            first("preset a { #[x(for_r = \"}\", y::<'a>)] } x"),
+            "preset a { #[x(for_r = \"}\", y::<'a>)] }"
+        );
+    }
+
+    #[test]
+    fn reports_unbalanced() {
+        assert_eq!(
+            preset_end("preset a { #[x) }"),
+            Err("unbalanced `)`".into())
+        );
+        assert_eq!(preset_end("preset a { #[x]"), Err("unclosed `{`".into()));
+        assert_eq!(
+            preset_end("preset a { /* }"),
+            Err("unterminated block comment".into())
+        );
+        assert_eq!(
+            preset_end("preset a { #[doc = \"}]"),
+            Err("unterminated string".into())
+        );
+    }
+}
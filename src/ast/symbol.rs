@@ -8,7 +8,7 @@ impl PartialEq<Symbol> for Path {
     fn eq(&self, word: &Symbol) -> bool { self.is_ident(word.0) }
 }
 
-impl<'a> PartialEq<Symbol> for &'a Path {
+impl PartialEq<Symbol> for &Path {
     fn eq(&self, word: &Symbol) -> bool { self.is_ident(word.0) }
 }
 
@@ -17,7 +17,7 @@ impl<T: ?Sized + AsRef<str>> PartialEq<T> for Symbol {
 }
 
 impl AsRef<str> for Symbol {
-    fn as_ref(&self) -> &str { &*self.0 }
+    fn as_ref(&self) -> &str { self.0 }
 }
 
 impl Debug for Symbol {
@@ -50,22 +50,32 @@ make_symbols! {
     CONTENT = "content",
     DEFAULT = "default",
     DENY_UNKNOWN_FIELDS = "deny_unknown_fields",
+    DEREF = "deref",
+    ERROR = "error",
     FIELD_IDENTIFIER = "field_identifier",
     FLATTEN = "flatten",
     FROM = "from",
     GETTER = "getter",
     SETTER = "setter",
+    SEPARATOR = "separator",
     INTO = "into",
+    KIND = "kind",
     OTHER = "other",
     RENAME = "rename",
     DISCR = "discr",
     DISPLAY = "display",
     RENAME_ALL = "rename_all",
     SKIP = "skip",
+    SKIP_DISPLAY = "skip_display",
+    SKIP_HIDDEN = "skip_hidden",
+    SKIP_ITER = "skip_iter",
+    SKIP_PARSE = "skip_parse",
     TAG = "tag",
     TRANSPARENT = "transparent",
     TRY_FROM = "try_from",
     UNTAGGED = "untagged",
+    VALIDATE = "validate",
+    VIS = "vis",
     VARIANT_IDENTIFIER = "variant_identifier",
     WITH = "with",
     REPR = "repr",
@@ -73,3 +83,60 @@ make_symbols! {
     CFG_ATTR = "cfg_attr",
     ATTR_NEST = "attrr_nest"
 }
+
+/// the key of `keys` closest to a misspelled `word`, when it is a few edits
+/// away
+pub fn suggest(word: &str, keys: &[Symbol]) -> Option<Symbol> {
+    keys.iter()
+        .map(|key| (edit_distance(word, key.0), *key))
+        .filter(|(distance, key)| *distance <= (key.0.len() / 3).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, key)| key)
+}
+
+/// levenshtein distance over chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let replace = prev[j] + (ca != *cb) as usize;
+            row.push(replace.min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("rename", "rename"), 0);
+        assert_eq!(edit_distance("renam", "rename"), 1);
+        assert_eq!(edit_distance("renme_all", "rename_all"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("é", "e"), 1);
+    }
+
+    #[test]
+    fn suggestions() {
+        let keys = [RENAME, RENAME_ALL, SKIP, SKIP_ITER, WITH];
+        let closest = |word| suggest(word, &keys).map(|key| key.0);
+
+        assert_eq!(closest("renam_all"), Some("rename_all"));
+        assert_eq!(closest("renam"), Some("rename"));
+        assert_eq!(closest("skp"), Some("skip"));
+        assert_eq!(closest("wiht"), None);
+        assert_eq!(closest("separator"), None);
+        assert_eq!(suggest("skip", &[]).map(|key| key.0), None);
+    }
+}
@@ -0,0 +1,591 @@
+use ast::{ColumnMacro, FieldsMacro, ValueMacro, VariantMacro};
+use std::{env, fs, path::PathBuf};
+use syn::{
+    punctuated::Punctuated, token, Error, Expr, ExprLit, Ident, Lit, LitInt,
+    LitStr, Result, Type,
+};
+
+/// `from "path/to/codes.csv"` source of `make_enum!` variants
+///
+/// the path is relative to `CARGO_MANIFEST_DIR`, the format is picked from
+/// the extension:
+///
+/// ```text
+/// # codes/opcodes.csv
+/// name,const,value,doc
+/// Nop,NOP,0,does nothing
+/// Load,LOAD,1,"loads a value, then halts"
+/// ```
+///
+/// ```text
+/// # codes/opcodes.toml
+/// [[variant]]
+/// name = "Nop"
+/// const = "NOP"
+/// value = 0
+/// doc = "does nothing"
+/// ```
+///
+/// only `name` is required, any other key must be a declared `columns(...)`
+/// entry; `&str` columns take the text verbatim, unquoted csv cells of other
+/// columns are parsed as rust expressions, toml values must be integers or
+/// booleans and a quoted csv cell or toml string is rejected there
+#[derive(Debug)]
+pub struct SourceMacro {
+    pub path: LitStr,
+}
+
+#[derive(Debug)]
+struct Row {
+    line: usize,
+    cells: Vec<(String, Cell)>,
+}
+
+#[derive(Debug)]
+enum Cell {
+    /// toml string or quoted csv cell
+    Str(String),
+    /// toml integer or boolean
+    Raw(String),
+    /// unquoted csv cell
+    Text(String),
+}
+
+impl SourceMacro {
+    pub fn full_path(&self) -> Result<PathBuf> {
+        let root = env::var("CARGO_MANIFEST_DIR").map_err(|_| {
+            Error::new(self.path.span(), "CARGO_MANIFEST_DIR is not set")
+        })?;
+
+        Ok(PathBuf::from(root).join(self.path.value()))
+    }
+
+    /// read the file and build one variant per row
+    pub fn load(
+        &self,
+        columns: &Punctuated<ColumnMacro, token::Comma>,
+    ) -> Result<Punctuated<VariantMacro, token::Comma>> {
+        let path = self.full_path()?;
+        let text = fs::read_to_string(&path).map_err(|e| {
+            Error::new(
+                self.path.span(),
+                format!("failed to read `{}`: {}", path.display(), e),
+            )
+        })?;
+
+        let rows = match path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => self.parse_csv(&text)?,
+            Some("toml") => self.parse_toml(&text)?,
+            _ => {
+                let path = &self.path;
+                return err!(path: "expected a `.csv` or `.toml` file");
+            },
+        };
+
+        rows.into_iter()
+            .map(|row| self.variant(row, columns))
+            .collect()
+    }
+
+    fn error(&self, line: usize, msg: impl AsRef<str>) -> Error {
+        Error::new(
+            self.path.span(),
+            format!("{}:{}: {}", self.path.value(), line, msg.as_ref()),
+        )
+    }
+
+    fn variant(
+        &self,
+        row: Row,
+        columns: &Punctuated<ColumnMacro, token::Comma>,
+    ) -> Result<VariantMacro> {
+        let span = self.path.span();
+        let line = row.line;
+
+        let mut ident = None;
+        let mut name = None;
+        let mut discriminant = None;
+        let mut attrs = Vec::new();
+        let mut values = Punctuated::new();
+
+        for (key, cell) in row.cells {
+            let text = match &cell {
+                Cell::Str(s) | Cell::Raw(s) | Cell::Text(s) => {
+                    s.trim().to_owned()
+                },
+            };
+
+            match key.as_str() {
+                "name" => ident = Some(self.ident(line, &key, &text)?),
+                _ if text.is_empty() => {},
+                "const" => name = Some(self.ident(line, &key, &text)?),
+                "value" => {
+                    if let Cell::Str(_) = cell {
+                        return Err(self.error(
+                            line,
+                            "`value` must be an integer, not a string",
+                        ));
+                    }
+                    discriminant =
+                        Some((token::Eq(span), self.integer(line, &text)?))
+                },
+                "doc" => {
+                    let doc = LitStr::new(&format!(" {}", text), span);
+                    attrs.push(parse_quote!(#[doc = #doc]));
+                },
+                _ => {
+                    let column = columns
+                        .iter()
+                        .find(|c| c.ident == key)
+                        .ok_or_else(|| {
+                            self.error(
+                                line,
+                                format!("unknown column `{}`", key),
+                            )
+                        })?;
+
+                    let expr = match (&cell, is_str(&column.ty)) {
+                        (Cell::Str(s), true) | (Cell::Text(s), true) => {
+                            Expr::Lit(ExprLit {
+                                attrs: Vec::new(),
+                                lit: Lit::Str(LitStr::new(s, span)),
+                            })
+                        },
+                        (Cell::Raw(_), true) => {
+                            return Err(self.error(
+                                line,
+                                format!("column `{}` expects a string", key),
+                            ))
+                        },
+                        (Cell::Str(_), false) => {
+                            return Err(self.error(
+                                line,
+                                format!(
+                                    "column `{}` is not a `&str`, expected an \
+                                     integer or boolean, not a string",
+                                    key
+                                ),
+                            ))
+                        },
+                        _ => self.expr(line, &key, &text)?,
+                    };
+
+                    values.push(ValueMacro {
+                        ident: column.ident.clone(),
+                        expr,
+                    });
+                },
+            }
+        }
+
+        let ident =
+            ident.ok_or_else(|| self.error(line, "missing variant `name`"))?;
+
+        if name.is_none() && !values.is_empty() {
+            return Err(self.error(line, "columns require a `const` name"));
+        }
+
+        Ok(VariantMacro {
+            attrs,
+            ident,
+            fields: FieldsMacro {
+                ident: name,
+                discriminant,
+                values,
+            },
+        })
+    }
+
+    fn ident(&self, line: usize, key: &str, text: &str) -> Result<Ident> {
+        syn::parse_str::<Ident>(text)
+            .map(|i| Ident::new(&i.to_string(), self.path.span()))
+            .map_err(|_| {
+                self.error(
+                    line,
+                    format!("invalid `{}` identifier `{}`", key, text),
+                )
+            })
+    }
+
+    fn expr(&self, line: usize, key: &str, text: &str) -> Result<Expr> {
+        syn::parse_str::<Expr>(text).map_err(|e| {
+            self.error(
+                line,
+                format!("invalid `{}` value `{}`: {}", key, text, e),
+            )
+        })
+    }
+
+    /// integer literal of the `value` column, negative with a leading `-`
+    fn integer(&self, line: usize, text: &str) -> Result<Expr> {
+        let digits = text.strip_prefix('-').unwrap_or(text).trim_start();
+        match syn::parse_str::<LitInt>(digits) {
+            Ok(_) => self.expr(line, "value", text),
+            Err(_) => Err(self.error(
+                line,
+                format!("`value` must be an integer literal, not `{}`", text),
+            )),
+        }
+    }
+
+    fn parse_csv(&self, text: &str) -> Result<Vec<Row>> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l))
+            .filter(|(_, l)| !l.trim().is_empty() && !l.starts_with('#'));
+
+        let header = match lines.next() {
+            Some((line, header)) => {
+                let header = self
+                    .split_csv(line, header)?
+                    .into_iter()
+                    .map(|cell| match cell {
+                        Cell::Str(s) | Cell::Raw(s) | Cell::Text(s) => s,
+                    })
+                    .collect::<Vec<_>>();
+                if !header.iter().any(|h| h == "name") {
+                    return Err(
+                        self.error(line, "csv header needs a `name` column")
+                    );
+                }
+                header
+            },
+            None => return Err(self.error(1, "missing csv header")),
+        };
+
+        lines
+            .map(|(line, l)| {
+                let fields = self.split_csv(line, l)?;
+                if fields.len() != header.len() {
+                    return Err(self.error(
+                        line,
+                        format!(
+                            "expected {} fields, found {}",
+                            header.len(),
+                            fields.len()
+                        ),
+                    ));
+                }
+
+                Ok(Row {
+                    line,
+                    cells: header.iter().cloned().zip(fields).collect(),
+                })
+            })
+            .collect()
+    }
+
+    fn split_csv(&self, line: usize, l: &str) -> Result<Vec<Cell>> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut chars = l.chars().peekable();
+        let mut quoted = false;
+        let mut was_quoted = false;
+
+        let cell = |field: &str, was_quoted| {
+            let field = field.trim().to_owned();
+            if was_quoted {
+                Cell::Str(field)
+            } else {
+                Cell::Text(field)
+            }
+        };
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                },
+                '"' => {
+                    quoted = !quoted;
+                    was_quoted = true;
+                },
+                ',' if !quoted => {
+                    fields.push(cell(&field, was_quoted));
+                    field.clear();
+                    was_quoted = false;
+                },
+                ch => field.push(ch),
+            }
+        }
+
+        if quoted {
+            return Err(self.error(line, "unterminated quoted field"));
+        }
+
+        fields.push(cell(&field, was_quoted));
+        Ok(fields)
+    }
+
+    fn parse_toml(&self, text: &str) -> Result<Vec<Row>> {
+        let mut rows: Vec<Row> = Vec::new();
+
+        for (i, l) in text.lines().enumerate() {
+            let line = i + 1;
+            let l = strip_toml_comment(l).trim();
+
+            if l.is_empty() {
+                continue;
+            }
+
+            if l.starts_with('[') {
+                if l != "[[variant]]" {
+                    return Err(self.error(line, "expected `[[variant]]`"));
+                }
+
+                rows.push(Row {
+                    line,
+                    cells: Vec::new(),
+                });
+                continue;
+            }
+
+            let row = rows.last_mut().ok_or_else(|| {
+                self.error(line, "expected `[[variant]]` before keys")
+            })?;
+
+            let eq = l
+                .find('=')
+                .ok_or_else(|| self.error(line, "expected `key = value`"))?;
+            let key = l[..eq].trim().trim_matches('"').to_owned();
+            let value = self.toml_value(line, l[eq + 1..].trim())?;
+
+            if row.cells.iter().any(|(k, _)| *k == key) {
+                return Err(
+                    self.error(line, format!("duplicate key `{}`", key))
+                );
+            }
+
+            row.cells.push((key, value));
+        }
+
+        Ok(rows)
+    }
+
+    fn toml_value(&self, line: usize, v: &str) -> Result<Cell> {
+        if v.len() >= 2 && v.starts_with('\'') && v.ends_with('\'') {
+            return Ok(Cell::Str(v[1..v.len() - 1].to_owned()));
+        }
+
+        if v.len() >= 2 && v.starts_with('"') && v.ends_with('"') {
+            let mut s = String::new();
+            let mut chars = v[1..v.len() - 1].chars();
+
+            while let Some(ch) = chars.next() {
+                if ch != '\\' {
+                    s.push(ch);
+                    continue;
+                }
+
+                s.push(match chars.next() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    _ => return Err(self.error(line, "unsupported escape")),
+                });
+            }
+
+            return Ok(Cell::Str(s));
+        }
+
+        // integers start with a digit, `0x10`, `1_000` and `8u8` included
+        let int = v.trim_start_matches(&['+', '-'][..]);
+        if v == "true"
+            || v == "false"
+            || (int.starts_with(|c: char| c.is_ascii_digit())
+                && int.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        {
+            return Ok(Cell::Raw(v.to_owned()));
+        }
+
+        Err(self.error(
+            line,
+            format!(
+                "unsupported value `{}`, expected string, integer or bool",
+                v
+            ),
+        ))
+    }
+}
+
+/// strip a `#` comment that is not inside a string
+fn strip_toml_comment(l: &str) -> &str {
+    let mut quote = None;
+    for (i, ch) in l.char_indices() {
+        match (ch, quote) {
+            ('"', None) | ('\'', None) => quote = Some(ch),
+            (ch, Some(q)) if ch == q => quote = None,
+            ('#', None) => return &l[..i],
+            _ => {},
+        }
+    }
+    l
+}
+
+/// `&str` and `&'static str` columns take the cell text verbatim
+fn is_str(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(r) => match &*r.elem {
+            Type::Path(p) => p.path.is_ident("str"),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::Span;
+
+    fn source() -> SourceMacro {
+        SourceMacro {
+            path: LitStr::new("codes.txt", Span::call_site()),
+        }
+    }
+
+    fn columns() -> Punctuated<ColumnMacro, token::Comma> {
+        parse_quote!(size: u8, label: &'static str)
+    }
+
+    fn text(cell: &Cell) -> (&str, bool) {
+        match cell {
+            Cell::Str(s) => (s, true),
+            Cell::Raw(s) | Cell::Text(s) => (s, false),
+        }
+    }
+
+    fn error<T>(result: Result<T>) -> String {
+        match result {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn csv_quoting() {
+        let cells = source().split_csv(1, r#"Load, "a, ""b""" ,3,"""#).unwrap();
+        let cells = cells.iter().map(text).collect::<Vec<_>>();
+
+        assert_eq!(
+            cells,
+            [
+                ("Load", false),
+                (r#"a, "b""#, true),
+                ("3", false),
+                ("", true),
+            ]
+        );
+        assert_eq!(
+            error(source().split_csv(4, r#"Load,"open"#)),
+            "codes.txt:4: unterminated quoted field"
+        );
+    }
+
+    #[test]
+    fn csv_lines() {
+        let rows = source()
+            .parse_csv("\n# opcodes\nname,const,size\n\nNop,NOP,1\n# end\n")
+            .unwrap();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].line, 5);
+        assert_eq!(
+            error(source().parse_csv("name,const\nNop,NOP,1\n")),
+            "codes.txt:2: expected 2 fields, found 3"
+        );
+        assert_eq!(
+            error(source().parse_csv("const\nNOP\n")),
+            "codes.txt:1: csv header needs a `name` column"
+        );
+    }
+
+    #[test]
+    fn toml_values() {
+        let s = source();
+
+        assert_eq!(
+            text(&s.toml_value(1, r#""a\"b\\c\n""#).unwrap()),
+            ("a\"b\\c\n", true)
+        );
+        assert_eq!(text(&s.toml_value(1, r"'a\n'").unwrap()), (r"a\n", true));
+        assert_eq!(text(&s.toml_value(1, "-0x1f").unwrap()), ("-0x1f", false));
+        assert_eq!(text(&s.toml_value(1, "true").unwrap()), ("true", false));
+        assert_eq!(
+            error(s.toml_value(2, r#""\A""#)),
+            "codes.txt:2: unsupported escape"
+        );
+        assert_eq!(
+            error(s.toml_value(3, "FOO")),
+            "codes.txt:3: unsupported value `FOO`, expected string, integer \
+             or bool"
+        );
+    }
+
+    #[test]
+    fn toml_lines() {
+        let rows = source()
+            .parse_toml(
+                "# codes\n\n[[variant]]\nname = \"Nop\" # no-op\nsize = 1\n\n\
+                 [[variant]]\nname = \"#\"\n",
+            )
+            .unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].cells.len(), 2);
+        assert_eq!(text(&rows[1].cells[0].1), ("#", true));
+        assert_eq!(
+            error(source().parse_toml("name = \"Nop\"\n")),
+            "codes.txt:1: expected `[[variant]]` before keys"
+        );
+        assert_eq!(
+            error(source().parse_toml("[[variant]]\nsize = 1\nsize = 2\n")),
+            "codes.txt:3: duplicate key `size`"
+        );
+    }
+
+    #[test]
+    fn column_types() {
+        let s = source();
+        let variant = |text: &str| {
+            let rows = s.parse_csv(text)?;
+            s.variant(rows.into_iter().next().unwrap(), &columns())
+        };
+
+        let ok = variant("name,const,size,label\nNop,NOP,1,\"a, b\"\n");
+        assert_eq!(ok.unwrap().fields.values.len(), 2);
+        assert_eq!(
+            error(variant("name,const,size\nNop,NOP,\"1\"\n")),
+            "codes.txt:2: column `size` is not a `&str`, expected an integer \
+             or boolean, not a string"
+        );
+        assert_eq!(
+            error(variant("name,const,value\nNop,NOP,\"1\"\n")),
+            "codes.txt:2: `value` must be an integer, not a string"
+        );
+        assert_eq!(
+            error(variant("name,const,value\nNop,NOP,A + B\n")),
+            "codes.txt:2: `value` must be an integer literal, not `A + B`"
+        );
+        assert_eq!(
+            error(variant("name,const,value\nNop,NOP,foo()\n")),
+            "codes.txt:2: `value` must be an integer literal, not `foo()`"
+        );
+        for value in &["-3", "0x1F", "1_000u16", "- 7"] {
+            let ok = variant(&format!("name,const,value\nNop,NOP,{}\n", value));
+            assert!(ok.unwrap().fields.discriminant.is_some(), "{}", value);
+        }
+
+        let rows = s.parse_toml(
+            "[[variant]]\nname = \"Nop\"\nconst = \"NOP\"\nlabel = 1\n",
+        );
+        assert_eq!(
+            error(s.variant(rows.unwrap().remove(0), &columns())),
+            "codes.txt:1: column `label` expects a string"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use ast::{get_container_attrs, repr_int, ContainerAttr, TaggedAttr};
+use proc_macro2::Span;
+use std::collections::HashSet;
+use syn::{
+    parse::{Parse, ParseStream},
+    Data, DeriveInput, Expr, Fields, Generics, Ident, Path, Result, Visibility,
+};
+
+/// ```
+///
+/// #[macro_use]
+/// extern crate mac_derive;
+///
+/// // the `Encode` and `Decode` traits, their impls for primitives, arrays,
+/// // `Vec`, `String`, `Option` and `Box`, `EncodeError` and `DecodeError` as
+/// // `mod codec`
+/// codec!();
+///
+/// use codec::{Decode, DecodeError, Encode, EncodeError};
+///
+/// #[derive(Tagged, Debug, PartialEq)]
+/// pub struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// // `[tag: u16][payload length: u32][payload]`, tags follow the
+/// // discriminants
+/// #[derive(Tagged, Debug, PartialEq)]
+/// #[repr(u16)]
+/// #[enum_repr(content = "u32")]
+/// pub enum Message {
+///     Ping = 1,
+///     Move(Point),
+///     Say { from: String, text: Vec<u8> } = 10,
+///     Batch(Vec<Message>),
+/// }
+///
+/// #[derive(Tagged, Debug, PartialEq)]
+/// #[enum_repr(content = "u8")]
+/// pub enum Short {
+///     Text(String),
+/// }
+///
+/// fn main() {
+///     let bytes = Message::Move(Point { x: 1, y: -1 }).to_bytes().unwrap();
+///     assert_eq!(&bytes[..6], &[2, 0, 8, 0, 0, 0]);
+///     assert_eq!(
+///         Message::from_bytes(&bytes),
+///         Ok(Message::Move(Point { x: 1, y: -1 }))
+///     );
+///
+///     assert_eq!(Message::Ping.to_bytes().unwrap(), vec![1, 0, 0, 0, 0, 0]);
+///     let say = Message::Say { from: "a".to_owned(), text: vec![1, 2] };
+///     let batch = Message::Batch(vec![say, Message::Ping]);
+///     assert_eq!(Message::from_bytes(&batch.to_bytes().unwrap()), Ok(batch));
+///
+///     assert_eq!(
+///         Message::from_bytes(&[3, 0, 0, 0, 0, 0]),
+///         Err(DecodeError::UnknownTag { ty: "Message", tag: Some(3) })
+///     );
+///
+///     // payloads longer than their `content` length do not encode
+///     assert_eq!(
+///         Short::Text("x".repeat(300)).to_bytes(),
+///         Err(EncodeError::PayloadTooLong { len: 304 })
+///     );
+/// }
+/// ```
+///
+/// the tag type is `tag = ".."`, else the `#[repr(..)]` of the enum, else
+/// `u8`; without `content` payloads are written without a length prefix
+///
+/// the generated impls use the module at `crate = "path"`, `crate::codec` by
+/// default
+#[derive(Debug)]
+pub struct Tagged {
+    pub attrs: HashSet<ContainerAttr>,
+    pub ident: Ident,
+    pub generics: Generics,
+    pub tag: Ident,
+    pub body: TaggedBody,
+}
+
+#[derive(Debug)]
+pub enum TaggedBody {
+    /// structs are their fields in order, with no tag
+    Struct(Fields),
+    Enum(Vec<TaggedVariant>),
+}
+
+#[derive(Debug)]
+pub struct TaggedVariant {
+    pub ident: Ident,
+    pub fields: Fields,
+    pub discriminant: Option<Expr>,
+}
+
+/// `codec!(vis mod name)`, `pub mod codec` when empty
+#[derive(Debug)]
+pub struct CodecMacro {
+    pub vis: Visibility,
+    pub ident: Ident,
+}
+
+impl Parse for Tagged {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let derive_input: DeriveInput = input.parse()?;
+        split_owned!(derive_input as attrs, ident, generics, data);
+
+        let repr = repr_int(&attrs);
+        let attrs = get_container_attrs::<TaggedAttr>(attrs)?
+            .into_iter()
+            .map(|TaggedAttr(a)| a)
+            .collect::<HashSet<_>>();
+        let tag = attrs
+            .iter()
+            .find_map(|a| match a {
+                ContainerAttr::Tag(tag) => Some(tag.clone()),
+                _ => None,
+            })
+            .or(repr)
+            .unwrap_or_else(|| Ident::new("u8", ident.span()));
+
+        let body = match data {
+            Data::Struct(data) => TaggedBody::Struct(data.fields),
+            Data::Enum(data) => TaggedBody::Enum(
+                data.variants
+                    .into_iter()
+                    .map(|var| TaggedVariant {
+                        ident: var.ident,
+                        fields: var.fields,
+                        discriminant: var.discriminant.map(|(_, expr)| expr),
+                    })
+                    .collect(),
+            ),
+            Data::Union(_) => {
+                return err!(ident: "only work for enum and struct")
+            },
+        };
+
+        Ok(Self {
+            attrs,
+            ident,
+            generics,
+            tag,
+            body,
+        })
+    }
+}
+
+impl Tagged {
+    /// integer type of the payload length prefix
+    pub fn content(&self) -> Option<&Ident> {
+        self.attrs.iter().find_map(|a| match a {
+            ContainerAttr::Content(ty) => Some(ty),
+            _ => None,
+        })
+    }
+
+    /// module generated by `codec!()`
+    pub fn codec(&self) -> Path {
+        self.attrs
+            .iter()
+            .find_map(|a| match a {
+                ContainerAttr::Crate(path) => Some(path.clone()),
+                _ => None,
+            })
+            .unwrap_or_else(|| parse_quote!(crate::codec))
+    }
+}
+
+impl Parse for CodecMacro {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.is_empty() {
+            return Ok(Self {
+                vis: parse_quote!(pub),
+                ident: Ident::new("codec", Span::call_site()),
+            });
+        }
+
+        let vis = input.parse()?;
+        input.parse::<Token![mod]>()?;
+
+        Ok(Self {
+            vis,
+            ident: input.parse()?,
+        })
+    }
+}
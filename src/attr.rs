@@ -1,27 +1,948 @@
-use syn::*;
+use ast::{unknown_key, AttributesBucket, EnumReprAttr, Preset, Presets, ID};
 use proc_macro2::TokenStream;
-use syn::parse::{Parse, ParseStream};
+use quote::ToTokens;
+use std::{collections::HashSet, convert::TryFrom};
+use syn::spanned::Spanned;
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    *,
+};
+use util::debug_expansion;
 
+/// `#[awe(...)]` arguments
+///
+/// a preset name expands to the attributes it bundles, see [`Preset`];
+/// `enum_repr(...)` on a module adds container defaults to each enum deriving
+/// `EnumRepr`, keys an enum sets itself are kept
+///
+/// ```
+///
+/// #[macro_use]
+/// extern crate mac_derive;
+///
+/// use mac_derive::awe;
+///
+/// #[awe(wire_enum)]
+/// pub enum Wire {
+///     Ping = 1,
+///     Pong = 2,
+/// }
+///
+/// #[awe(enum_repr(rename_all = "kebab-case"))]
+/// mod codes {
+///     #[derive(EnumRepr)]
+///     pub enum Http {
+///         NotFound = 404,
+///     }
+///
+///     #[derive(EnumRepr)]
+///     #[enum_repr(rename_all = "UPPERCASE")]
+///     pub enum Status {
+///         Ok,
+///     }
+/// }
+///
+/// // `ShapeEnum` with `Circle(Circle)` and `Square(Square)` implementing
+/// // `Shape` by matching
+/// #[awe(dispatch(Circle, Square))]
+/// pub trait Shape {
+///     fn area(&self) -> f64;
+///     fn scale(&mut self, by: f64);
+/// }
+///
+/// pub struct Circle(f64);
+/// pub struct Square(f64);
+///
+/// impl Shape for Circle {
+///     fn area(&self) -> f64 { 3.14 * self.0 * self.0 }
+///     fn scale(&mut self, by: f64) { self.0 *= by }
+/// }
+///
+/// impl Shape for Square {
+///     fn area(&self) -> f64 { self.0 * self.0 }
+///     fn scale(&mut self, by: f64) { self.0 *= by }
+/// }
+///
+/// // inherent `len` and `get` forwarding to `self.items`
+/// #[awe(delegate(to = items, methods(
+///     pub fn len(&self) -> usize;
+///     pub fn get(&self, i: usize) -> Option<&u8>;
+/// )))]
+/// pub struct Bytes {
+///     items: Vec<u8>,
+/// }
+///
+/// // signatures copied from a `delegatable` trait declared earlier, the field
+/// // only needs methods of the same names
+/// #[awe(delegatable)]
+/// pub trait Collection {
+///     fn len(&self) -> usize;
+///     fn is_empty(&self) -> bool;
+///     fn get(&self, i: usize) -> Option<&u8>;
+/// }
+///
+/// #[awe(delegate(to = inner, trait = Collection, methods(len, is_empty, get)))]
+/// pub struct Buffer {
+///     inner: Vec<u8>,
+/// }
+///
+/// pub struct Wrapper(Vec<u8>);
+///
+/// #[awe(delegate(to = 0))]
+/// impl Wrapper {
+///     pub fn is_empty(&self) -> bool;
+///     pub fn push(&mut self, b: u8);
+/// }
+///
+/// fn main() {
+///     let bytes = Bytes { items: vec![1, 2] };
+///     assert_eq!(bytes.len(), 2);
+///     assert_eq!(bytes.get(1), Some(&2));
+///
+///     let buffer = Buffer { inner: vec![3] };
+///     assert_eq!(buffer.len(), 1);
+///     assert!(!buffer.is_empty());
+///     assert_eq!(buffer.get(0), Some(&3));
+///
+///     let mut w = Wrapper(Vec::new());
+///     w.push(1);
+///     assert!(!w.is_empty());
+///
+///     let mut shapes = vec![ShapeEnum::from(Circle(1.)), Square(2.).into()];
+///     shapes.iter_mut().for_each(|s| s.scale(2.));
+///     assert_eq!(shapes[0].area(), 3.14 * 4.);
+///     assert_eq!(shapes[1].area(), 16.);
+///
+///     let w = Wire::Ping;
+///     assert_eq!(w, w.clone());
+///     assert_eq!(Wire::Pong as u16, 2);
+/// }
+/// ```
 #[derive(Debug)]
 struct Args {
-    attrs: Vec<Attribute>
+    args: Punctuated<Arg, Token![,]>,
+}
+
+#[derive(Debug)]
+enum Arg {
+    /// `#[awe(wire_enum)]` expands to the attributes of a preset
+    Preset(Ident),
+    /// `#[awe(enum_repr(...))]` container defaults for every `EnumRepr`
+    /// enum of a module
+    EnumRepr(MetaList),
+    /// `#[awe(dispatch(A, B))]` on a trait generates an enum of the members
+    /// implementing the trait by forwarding
+    Dispatch(Ident, Punctuated<Type, Token![,]>),
+    /// `#[awe(delegate(to = field, methods(..)))]` forwards methods to a field
+    Delegate(Delegate),
+    /// `#[awe(delegatable)]` on a trait lets `delegate(trait = ..)` copy the
+    /// signatures of its methods
+    Delegatable(kw::delegatable),
+}
+
+#[derive(Debug)]
+struct Delegate {
+    ident: Ident,
+    to: Member,
+    /// `trait = Path` naming a `delegatable` trait the bare names are taken
+    /// from
+    trait_: Option<Path>,
+    methods: Vec<DelegateMethod>,
+    /// items of the trait, passed back by the macro of a `delegatable` trait
+    signatures: Option<Vec<TraitItem>>,
+}
+
+#[derive(Debug)]
+enum DelegateMethod {
+    /// a bodiless method of the impl block, or a method of the trait
+    Name(Ident),
+    /// `fn len(&self) -> usize;`
+    Sig(Box<ImplItemMethod>),
+}
+
+mod kw {
+    custom_keyword!(to);
+    custom_keyword!(methods);
+    custom_keyword!(signatures);
+    custom_keyword!(delegatable);
+}
+
+impl Parse for Delegate {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        let ident = input.parse()?;
+        parenthesized!(content in input);
+
+        content.parse::<kw::to>()?;
+        content.parse::<Token![=]>()?;
+        let to = content.parse()?;
+        let mut trait_ = None;
+        let mut methods = Vec::new();
+        let mut signatures = None;
+
+        while content.peek(Token![,]) {
+            content.parse::<Token![,]>()?;
+
+            let lookahead = content.lookahead1();
+            if content.is_empty() {
+                break;
+            } else if lookahead.peek(Token![trait]) {
+                content.parse::<Token![trait]>()?;
+                content.parse::<Token![=]>()?;
+                trait_ = Some(content.parse()?);
+            } else if lookahead.peek(kw::methods) {
+                let list;
+                content.parse::<kw::methods>()?;
+                parenthesized!(list in content);
+
+                while !list.is_empty() {
+                    // signatures start with a keyword, `fn`, `pub`, `unsafe`...
+                    if list.peek(Ident) {
+                        methods.push(DelegateMethod::Name(list.parse()?));
+                    } else {
+                        let method = list.parse::<ImplItemMethod>()?;
+                        if !is_bodiless(&method) {
+                            let m = &method.sig.ident;
+                            return err!(m: "expected a signature ending with `;`");
+                        }
+                        methods.push(DelegateMethod::Sig(Box::new(method)));
+                    }
+
+                    if list.peek(Token![,]) {
+                        list.parse::<Token![,]>()?;
+                    }
+                }
+            } else if lookahead.peek(kw::signatures) {
+                let items;
+                content.parse::<kw::signatures>()?;
+                braced!(items in content);
+
+                let mut list = Vec::new();
+                while !items.is_empty() {
+                    list.push(items.parse()?);
+                }
+                signatures = Some(list);
+            } else {
+                return Err(lookahead.error());
+            }
+        }
+
+        if !content.is_empty() {
+            return Err(
+                content.error("expected `trait = ..` or `methods(...)`")
+            );
+        }
+
+        Ok(Self {
+            ident,
+            to,
+            trait_,
+            methods,
+            signatures,
+        })
+    }
 }
 
 impl Parse for Args {
     fn parse(input: ParseStream) -> Result<Self> {
         Ok(Args {
-            attrs: input.call(Attribute::parse_outer)?
+            args: input.parse_terminated(Arg::parse)?,
         })
     }
 }
 
-pub fn awe(
-	attr: TokenStream,
-	input: TokenStream
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.peek(kw::delegatable) && !input.peek2(token::Paren) {
+            return Ok(Arg::Delegatable(input.parse()?));
+        }
+
+        if !input.peek2(token::Paren) {
+            return Ok(Arg::Preset(input.parse()?));
+        }
+
+        if input.fork().parse::<Ident>()? == "delegate" {
+            return Ok(Arg::Delegate(input.parse()?));
+        }
+
+        if input.fork().parse::<Ident>()? == "dispatch" {
+            let content;
+            let ident = input.parse()?;
+            parenthesized!(content in input);
+
+            return Ok(Arg::Dispatch(
+                ident,
+                content.parse_terminated(Type::parse)?,
+            ));
+        }
+
+        match input.parse::<Meta>()? {
+            Meta::List(list) if list.path == ID => {
+                // report invalid defaults at the awe attribute itself
+                for nested in &list.nested {
+                    match nested {
+                        NestedMeta::Meta(m)
+                            if !EnumReprAttr::KEYS
+                                .iter()
+                                .any(|key| m.path() == key) =>
+                        {
+                            return Err(unknown_key::<EnumReprAttr>(
+                                m.path(),
+                                false,
+                            ));
+                        },
+                        NestedMeta::Meta(Meta::NameValue(m)) => {
+                            EnumReprAttr::try_from(m.clone())?;
+                        },
+                        NestedMeta::Meta(Meta::List(m)) => {
+                            EnumReprAttr::try_from(m.clone())?;
+                        },
+                        NestedMeta::Meta(Meta::Path(m)) => {
+                            EnumReprAttr::try_from(m.clone())?;
+                        },
+                        m => {
+                            return err!(
+                                m: "unexpected attribute {}",
+                                m.to_token_stream()
+                            )
+                        },
+                    }
+                }
+
+                Ok(Arg::EnumRepr(list))
+            },
+            m => err!(m: "unexpected awe argument {}", m.to_token_stream()),
+        }
+    }
+}
+
+pub fn awe(attr: TokenStream, input: TokenStream) -> Result<TokenStream> {
+    let args = syn::parse2::<Args>(attr)?;
+    let mut item = syn::parse2::<Item>(input.clone())?;
+    let mut extra = TokenStream::new();
+    let mut presets = None;
+
+    for arg in &args.args {
+        match arg {
+            Arg::Preset(ident) => {
+                if presets.is_none() {
+                    presets = Some(Presets::load(ident.span())?);
+                }
+
+                let presets = presets.as_ref().unwrap();
+                let preset = presets.get(ident).ok_or_else(|| {
+                    Error::new(
+                        ident.span(),
+                        format!(
+                            "unknown preset `{}` in `{}`, expected one of: {}",
+                            ident,
+                            presets.path.display(),
+                            presets.names()
+                        ),
+                    )
+                })?;
+
+                apply_preset(&mut item, preset)?;
+            },
+            Arg::EnumRepr(defaults) => apply_enum_repr(&mut item, defaults)?,
+            Arg::Dispatch(ident, members) => match &item {
+                Item::Trait(t) => extra.extend(dispatch(t, members)?),
+                _ => return err!(ident: "`dispatch` only works on a trait"),
+            },
+            Arg::Delegatable(kw) => match &item {
+                Item::Trait(t) => extra.extend(delegatable(t)),
+                _ => return err!(kw: "`delegatable` only works on a trait"),
+            },
+            Arg::Delegate(delegate) => {
+                if let Some(tokens) = apply_delegate(&mut item, delegate)? {
+                    extra.extend(tokens);
+                }
+            },
+        }
+    }
+
+    // rebuild whenever the presets file changes
+    if let Some(presets) = &presets {
+        let path = presets.path.to_string_lossy();
+        extra.extend(quote! {
+            const _: &[u8] = include_bytes!(#path);
+        });
+    }
+
+    let name = item_ident(&item)
+        .map(|ident| ident.to_string())
+        .unwrap_or_else(|| "item".into());
+    let output = quote! {
+        #item
+        #extra
+    };
+
+    debug_expansion("awe", &name, &input, &args, &output);
+    Ok(output)
+}
+
+/// prepend the preset attributes, attributes the item already sets win
+/// except for `derive` and `doc` which accumulate and `enum_repr` which is
+/// merged per key; traits the item derives itself are not derived again
+fn apply_preset(item: &mut Item, preset: &Preset) -> Result<()> {
+    let attrs = match item_attrs(item) {
+        Some(attrs) => attrs,
+        None => {
+            let ident = &preset.ident;
+            return err!(ident: "preset `{}` cannot be applied to this item", ident);
+        },
+    };
+
+    let derived = derive_paths(attrs)
+        .into_iter()
+        .filter_map(|p| p.segments.last().map(|s| s.ident.clone()))
+        .collect::<HashSet<_>>();
+    let keys = enum_repr_keys(attrs)?;
+
+    let mut merged = Vec::new();
+    for p in &preset.attrs {
+        if p.path.is_ident("derive") {
+            let paths = derive_paths(std::slice::from_ref(p))
+                .into_iter()
+                .filter(|path| {
+                    path.segments
+                        .last()
+                        .is_none_or(|s| !derived.contains(&s.ident))
+                })
+                .collect::<Vec<_>>();
+
+            if !paths.is_empty() {
+                merged.push(parse_quote!(#[derive(#(#paths),*)]));
+            }
+        } else if p.path == ID {
+            let nested = match p.parse_meta()? {
+                Meta::List(list) => list.nested,
+                m => return err!(m: "expected #[enum_repr(...)]"),
+            };
+            let missing = nested
+                .into_iter()
+                .filter(|n| match n {
+                    NestedMeta::Meta(m) => !keys.contains(m.path()),
+                    _ => true,
+                })
+                .collect::<Vec<_>>();
+
+            if !missing.is_empty() {
+                merged.push(parse_quote!(#[enum_repr(#(#missing),*)]));
+            }
+        } else if p.path.is_ident("doc")
+            || !attrs.iter().any(|a| a.path == p.path)
+        {
+            merged.push(p.clone());
+        }
+    }
+
+    merged.append(attrs);
+    *attrs = merged;
+
+    Ok(())
+}
+
+/// add the `enum_repr` defaults to the item, or to every item of a module,
+/// deriving `EnumRepr`; keys the enum already sets are left alone
+fn apply_enum_repr(item: &mut Item, defaults: &MetaList) -> Result<()> {
+    match item {
+        Item::Enum(e) if derives_enum_repr(&e.attrs) => {
+            let set = enum_repr_keys(&e.attrs)?;
+
+            let missing = defaults
+                .nested
+                .iter()
+                .filter(|n| match n {
+                    NestedMeta::Meta(m) => !set.contains(m.path()),
+                    _ => false,
+                })
+                .collect::<Vec<_>>();
+
+            if !missing.is_empty() {
+                e.attrs.push(parse_quote!(#[enum_repr(#(#missing),*)]));
+            }
+        },
+        Item::Mod(ItemMod {
+            content: Some((_, items)),
+            ..
+        }) => {
+            for item in items {
+                apply_enum_repr(item, defaults)?;
+            }
+        },
+        _ => {},
+    }
+
+    Ok(())
+}
+
+/// `enum TraitEnum { A(A), .. }` with `From` for each member and the trait
+/// implemented by matching on the member
+fn dispatch(
+    t: &ItemTrait,
+    members: &Punctuated<Type, Token![,]>,
 ) -> Result<TokenStream> {
-    let attr = syn::parse2::<Args>(attr)?;
-	dump!(attr);
-    dump!(input);
+    let vis = &t.vis;
+    let trait_ = &t.ident;
+    let ident = Ident::new(&format!("{}Enum", trait_), trait_.span());
+    let (impl_generics, ty_generics, where_clause) =
+        t.generics.split_for_impl();
+
+    let variants = members
+        .iter()
+        .map(|ty| match ty {
+            Type::Path(TypePath { path, .. }) => {
+                Ok(path.segments.last().unwrap().ident.clone())
+            },
+            ty => {
+                err!(ty: "expected a type path, found {}", ty.to_token_stream())
+            },
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut seen = HashSet::new();
+    for var in &variants {
+        if !seen.insert(var) {
+            return err!(var: "`{}` is listed twice", var);
+        }
+    }
+
+    let members = members.iter().collect::<Vec<_>>();
+    let doc =
+        format!("Static dispatch over the implementors of [`{}`].", trait_);
+
+    let methods = t
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            TraitItem::Method(m) => match m.sig.receiver() {
+                Some(_) => Some(Ok(m)),
+                // associated fns with a body keep their default
+                None if m.default.is_some() => None,
+                None => {
+                    let m = &m.sig.ident;
+                    Some(err!(m: "cannot dispatch `{}` without a self receiver", m))
+                },
+            },
+            TraitItem::Const(c) if c.default.is_some() => None,
+            TraitItem::Const(c) => {
+                let c = &c.ident;
+                Some(err!(c: "cannot dispatch associated const `{}`", c))
+            },
+            TraitItem::Type(ty) => {
+                let ty = &ty.ident;
+                Some(err!(ty: "cannot dispatch associated type `{}`", ty))
+            },
+            _ => None,
+        })
+        .map(|m| {
+            let mut sig = m?.sig.clone();
+            let name = &sig.ident.clone();
+            let args = forward_args(&mut sig);
 
-    Ok(quote! { #input })
+            let vars = &variants;
+            let args = quote! { #(#args),* };
+
+            Ok(quote! {
+                #sig {
+                    match self {
+                        #(
+                            #ident::#vars(v) => {
+                                <#members as #trait_ #ty_generics>::#name(v, #args)
+                            },
+                        )*
+                    }
+                }
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let vars = &variants;
+    Ok(quote! {
+        #[doc = #doc]
+        #vis enum #ident {
+            #( #vars(#members), )*
+        }
+
+        #(
+            impl ::std::convert::From<#members> for #ident {
+                fn from(v: #members) -> Self {
+                    #ident::#vars(v)
+                }
+            }
+        )*
+
+        impl #impl_generics #trait_ #ty_generics for #ident #where_clause {
+            #(#methods)*
+        }
+    })
+}
+
+/// give bodiless methods of an impl block, or the signatures listed in the
+/// attribute, a body forwarding to the field
+///
+/// with `trait = Path` the bare names are looked up in the trait: the macro
+/// generated by `#[awe(delegatable)]` passes its items back in an inherent
+/// impl carrying `signatures { .. }`
+fn apply_delegate(
+    item: &mut Item,
+    delegate: &Delegate,
+) -> Result<Option<TokenStream>> {
+    let to = &delegate.to;
+    let names = delegate
+        .methods
+        .iter()
+        .filter_map(|m| match m {
+            DelegateMethod::Name(name) => Some(name),
+            DelegateMethod::Sig(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let sigs = delegate.methods.iter().filter_map(|m| match m {
+        DelegateMethod::Sig(sig) => Some((**sig).clone()),
+        DelegateMethod::Name(_) => None,
+    });
+
+    if let (Some(trait_), Some(items)) =
+        (&delegate.trait_, &delegate.signatures)
+    {
+        let i = match item {
+            Item::Impl(i) => i,
+            _ => unreachable!("signatures are passed back in an impl block"),
+        };
+
+        for name in &names {
+            let found = items.iter().any(|item| match item {
+                TraitItem::Method(m) => m.sig.ident == **name,
+                _ => false,
+            });
+
+            if !found {
+                return err!(
+                    name: "`{}` is not a method of `{}`",
+                    name,
+                    trait_.to_token_stream()
+                );
+            }
+        }
+
+        for item in items {
+            if let TraitItem::Method(m) = item {
+                if names.is_empty() || names.iter().any(|n| **n == m.sig.ident)
+                {
+                    let attrs = &m.attrs;
+                    let mut m = ImplItemMethod {
+                        attrs: take!(attrs, "doc").cloned().collect(),
+                        vis: parse_quote!(pub),
+                        defaultness: None,
+                        sig: m.sig.clone(),
+                        block: parse_quote!({}),
+                    };
+                    forward_body(&mut m, to, None)?;
+                    i.items.push(ImplItem::Method(m));
+                }
+            }
+        }
+
+        return Ok(None);
+    }
+
+    // the trait macro calls `awe` again with the signatures
+    let callback = |header: TokenStream| {
+        let trait_ = delegate.trait_.as_ref()?;
+        let ident = &trait_.segments.last()?.ident;
+        let ident =
+            Ident::new(&format!("__awe_delegate_{}", ident), ident.span());
+
+        Some(quote! {
+            #ident! {
+                (to = #to, trait = #trait_, methods(#(#names),*))
+                #header
+            }
+        })
+    };
+
+    match item {
+        Item::Struct(s) => {
+            let ident = &s.ident;
+            let (impl_generics, ty_generics, where_clause) =
+                s.generics.split_for_impl();
+
+            let mut output = quote!();
+            if let Some(callback) = callback(quote! {
+                impl #impl_generics #ident #ty_generics #where_clause
+            }) {
+                output.extend(callback);
+            } else if let Some(name) = names.first() {
+                return err!(
+                    name: "give the signature of `{}` on a struct, e.g. \
+                           `methods(fn {}(&self) -> T;)`, or the \
+                           `delegatable` trait declaring it with `trait = ..`",
+                    name,
+                    name
+                );
+            }
+
+            let methods = sigs
+                .map(|mut m| {
+                    forward_body(&mut m, to, None)?;
+                    Ok(m)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            if !methods.is_empty() {
+                output.extend(quote! {
+                    impl #impl_generics #ident #ty_generics #where_clause {
+                        #(#methods)*
+                    }
+                });
+            }
+
+            Ok(Some(output))
+        },
+        Item::Impl(i) => {
+            let trait_ = i.trait_.as_ref().map(|(_, path, _)| path.clone());
+
+            if delegate.trait_.is_some() {
+                if let Some(path) = &trait_ {
+                    return err!(
+                        path: "`delegate(trait = ..)` adds inherent methods, \
+                               it does not work on a trait impl"
+                    );
+                }
+
+                let self_ty = &i.self_ty;
+                let (impl_generics, _, where_clause) =
+                    i.generics.split_for_impl();
+                let callback = callback(quote! {
+                    impl #impl_generics #self_ty #where_clause
+                });
+
+                let forwarded = i
+                    .items
+                    .iter_mut()
+                    .filter_map(|item| match item {
+                        ImplItem::Method(m) if is_bodiless(m) => Some(m),
+                        _ => None,
+                    })
+                    .map(|m| forward_body(m, to, None));
+                for result in forwarded {
+                    result?;
+                }
+
+                for mut m in sigs {
+                    forward_body(&mut m, to, None)?;
+                    i.items.push(ImplItem::Method(m));
+                }
+
+                return Ok(callback);
+            }
+
+            for name in &names {
+                let found = i.items.iter().any(|item| match item {
+                    ImplItem::Method(m) => {
+                        m.sig.ident == **name && is_bodiless(m)
+                    },
+                    _ => false,
+                });
+
+                if !found {
+                    return err!(
+                        name: "`{}` is not a bodiless method of this impl, \
+                               declare `fn {}(..) -> ..;` or give its \
+                               signature in `methods(...)`",
+                        name,
+                        name
+                    );
+                }
+            }
+
+            for item in &mut i.items {
+                if let ImplItem::Method(m) = item {
+                    let listed = names.is_empty()
+                        || names.iter().any(|n| **n == m.sig.ident);
+
+                    if listed && is_bodiless(m) {
+                        forward_body(m, to, trait_.as_ref())?;
+                    }
+                }
+            }
+
+            for mut m in sigs {
+                forward_body(&mut m, to, trait_.as_ref())?;
+                i.items.push(ImplItem::Method(m));
+            }
+
+            Ok(None)
+        },
+        _ => {
+            let ident = &delegate.ident;
+            err!(ident: "`delegate` only works on a struct or an impl block")
+        },
+    }
+}
+
+/// `macro_rules! __awe_delegate_Trait` passing the items of the trait back to
+/// `delegate(trait = Trait)`
+///
+/// the macro is found by textual scope, so the trait must come before the
+/// delegating items, in the same module or a parent one, or in a module
+/// marked `#[macro_use]`
+fn delegatable(t: &ItemTrait) -> TokenStream {
+    let ident =
+        Ident::new(&format!("__awe_delegate_{}", t.ident), t.ident.span());
+    let items = &t.items;
+
+    quote! {
+        #[doc(hidden)]
+        macro_rules! #ident {
+            (($($args:tt)*) $($header:tt)*) => {
+                #[::mac_derive::awe(delegate($($args)*, signatures { #(#items)* }))]
+                $($header)* {}
+            };
+        }
+    }
+}
+
+/// `self.field.method(args)`, or `Trait::method(&self.field, args)` inside
+/// a trait impl
+pub fn forward_body(
+    m: &mut ImplItemMethod,
+    to: &Member,
+    trait_: Option<&Path>,
+) -> Result<()> {
+    let name = m.sig.ident.clone();
+    let args = forward_args(&mut m.sig);
+
+    let field = match m.sig.receiver() {
+        Some(FnArg::Receiver(Receiver {
+            reference: Some(_),
+            mutability: Some(_),
+            ..
+        })) => quote! { &mut self.#to },
+        Some(FnArg::Receiver(Receiver {
+            reference: Some(_), ..
+        })) => quote! { &self.#to },
+        Some(FnArg::Receiver(_)) => quote! { self.#to },
+        _ => {
+            return err!(name: "cannot delegate `{}` without a self receiver", name)
+        },
+    };
+
+    m.block = match trait_ {
+        Some(trait_) => parse_quote!({ #trait_::#name(#field, #(#args),*) }),
+        None => parse_quote!({ self.#to.#name(#(#args),*) }),
+    };
+
+    Ok(())
+}
+
+/// name the typed arguments `__argN` so they can be forwarded
+fn forward_args(sig: &mut Signature) -> Vec<Ident> {
+    let span = sig.ident.span();
+
+    sig.inputs
+        .iter_mut()
+        .filter_map(|arg| match arg {
+            FnArg::Typed(arg) => Some(arg),
+            FnArg::Receiver(_) => None,
+        })
+        .enumerate()
+        .map(|(i, arg)| {
+            let ident = Ident::new(&format!("__arg{}", i), span);
+            *arg.pat = parse_quote!(#ident);
+            ident
+        })
+        .collect()
+}
+
+/// `fn f();` inside an impl block, accepted by the parser for macro input
+fn is_bodiless(m: &ImplItemMethod) -> bool {
+    match m.block.stmts.as_slice() {
+        [Stmt::Item(Item::Verbatim(tokens))] => tokens.to_string() == ";",
+        _ => false,
+    }
+}
+
+/// `#[derive(.., EnumRepr, ..)]` among the attributes
+fn derives_enum_repr(attrs: &[Attribute]) -> bool {
+    derive_paths(attrs)
+        .iter()
+        .any(|p| p.segments.last().is_some_and(|s| s.ident == "EnumRepr"))
+}
+
+/// paths listed in the `#[derive(...)]` attributes
+fn derive_paths(attrs: &[Attribute]) -> Vec<Path> {
+    take!(attrs, "derive")
+        .filter_map(|a| match a.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|n| match n {
+            NestedMeta::Meta(Meta::Path(p)) => Some(p),
+            _ => None,
+        })
+        .collect()
+}
+
+/// keys set by the `#[enum_repr(...)]` attributes
+fn enum_repr_keys(attrs: &[Attribute]) -> Result<HashSet<Path>> {
+    attrs
+        .iter()
+        .filter(|a| a.path == ID)
+        .map(|a| match a.parse_meta()? {
+            Meta::List(list) => Ok(list
+                .nested
+                .into_iter()
+                .filter_map(|n| match n {
+                    NestedMeta::Meta(m) => Some(m.path().clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()),
+            m => err!(m: "expected #[enum_repr(...)]"),
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(|keys| keys.into_iter().flatten().collect())
+}
+
+fn item_ident(item: &Item) -> Option<&Ident> {
+    match item {
+        Item::Const(i) => Some(&i.ident),
+        Item::Enum(i) => Some(&i.ident),
+        Item::ExternCrate(i) => Some(&i.ident),
+        Item::Fn(i) => Some(&i.sig.ident),
+        Item::Macro2(i) => Some(&i.ident),
+        Item::Mod(i) => Some(&i.ident),
+        Item::Static(i) => Some(&i.ident),
+        Item::Struct(i) => Some(&i.ident),
+        Item::Trait(i) => Some(&i.ident),
+        Item::TraitAlias(i) => Some(&i.ident),
+        Item::Type(i) => Some(&i.ident),
+        Item::Union(i) => Some(&i.ident),
+        _ => None,
+    }
+}
+
+fn item_attrs(item: &mut Item) -> Option<&mut Vec<Attribute>> {
+    match item {
+        Item::Const(i) => Some(&mut i.attrs),
+        Item::Enum(i) => Some(&mut i.attrs),
+        Item::ExternCrate(i) => Some(&mut i.attrs),
+        Item::Fn(i) => Some(&mut i.attrs),
+        Item::ForeignMod(i) => Some(&mut i.attrs),
+        Item::Impl(i) => Some(&mut i.attrs),
+        Item::Macro(i) => Some(&mut i.attrs),
+        Item::Macro2(i) => Some(&mut i.attrs),
+        Item::Mod(i) => Some(&mut i.attrs),
+        Item::Static(i) => Some(&mut i.attrs),
+        Item::Struct(i) => Some(&mut i.attrs),
+        Item::Trait(i) => Some(&mut i.attrs),
+        Item::TraitAlias(i) => Some(&mut i.attrs),
+        Item::Type(i) => Some(&mut i.attrs),
+        Item::Union(i) => Some(&mut i.attrs),
+        Item::Use(i) => Some(&mut i.attrs),
+        _ => None,
+    }
 }
@@ -1,20 +1,963 @@
-use super::{ast::Enum, util::*};
-use proc_macro2::TokenStream;
-use syn::Result;
+use super::{
+    ast::{
+        variant_name, Accessors, ContainerAttr, Enum, Getter, Newtype, Setter,
+        Skip, Tagged, TaggedBody, Variant, VariantAttr,
+    },
+    util::*,
+};
+use proc_macro2::{Span, TokenStream};
+use syn::{Fields, Ident, Path, Result, Type};
 
 pub fn enum_repr(input: TokenStream) -> Result<TokenStream> {
-    let input = syn::parse2::<Enum>(input)?;
-    _enum_repr(&input)
+    let parsed = syn::parse2::<Enum>(input.clone())?;
+    let output = _enum_repr(&parsed)?;
+
+    debug_expansion("enum_repr", &parsed.ident, &input, &parsed, &output);
+    Ok(output)
 }
 
 fn _enum_repr(input: &Enum) -> Result<TokenStream> {
-    //split!(input as attrs, ident, generics, variants);
-    Ok(wrap_in_const(None, "ENUM_REPR", &input.ident, quote! {}))
+    split!(input as ident, variants);
+    let nl_path = input.attrs.iter().find_map(|a| match a {
+        ContainerAttr::Crate(path) => Some(path),
+        _ => None,
+    });
+
+    let kind = input.attrs.iter().find_map(|a| match a {
+        ContainerAttr::Kind(kind) => Some(kind),
+        _ => None,
+    });
+
+    let flatten = variants.iter().any(|v| input.flatten(v));
+
+    for var in variants {
+        let name = &var.ident;
+        if input.flatten(var) {
+            if nested_ty(var).is_none() {
+                return err!(
+                    name: "`flatten` needs one unnamed field, like `{}::{}(Nested)`",
+                    ident,
+                    name
+                );
+            }
+            if var.attrs.iter().any(|a| matches!(a, VariantAttr::With(_))) {
+                return err!(name: "`flatten` variants cannot have `with`");
+            }
+        } else if flatten && !var.fields.is_empty() {
+            return err!(
+                name: "`{}::{}` has fields, the other variants of an enum with \
+                       `flatten` variants must be unit",
+                ident,
+                name
+            );
+        }
+    }
+
+    // without `kind`, enums with fields expand to nothing as they used to
+    let fields = variants.iter().any(|v| !v.fields.is_empty());
+    if kind.is_none() && !flatten && fields {
+        return Ok(quote! {});
+    }
+
+    let code = if flatten {
+        enum_repr_flatten(input)
+    } else if kind.is_none() {
+        enum_repr_impls(input, ident)
+    } else {
+        quote! {}
+    };
+
+    let kind = match kind {
+        Some(kind) => kind,
+        None => return Ok(wrap_in_const(nl_path, "ENUM_REPR", ident, code)),
+    };
+
+    split!(input as vis, generics, repr);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let declare = variants.iter().map(|v| {
+        split!(v as cfgs, ident);
+        let discriminant = v.discriminant.as_ref().map(|e| quote! { = #e });
+        let hidden = match v.hidden {
+            true => quote! { #[doc(hidden)] },
+            false => quote! {},
+        };
+        quote! { #(#cfgs)* #hidden #ident #discriminant, }
+    });
+    let repr = repr.as_ref().map(|repr| quote! { #[repr(#repr)] });
+    let doc = format!("Fieldless variants of [`{}`].", ident);
+
+    let arms = variants.iter().map(|v| {
+        split!(v as cfgs, fields);
+        let var = &v.ident;
+        let pattern = match fields {
+            Fields::Named(_) => quote! { { .. } },
+            Fields::Unnamed(_) => quote! { (..) },
+            Fields::Unit => quote! {},
+        };
+        quote! { #(#cfgs)* #ident::#var #pattern => #kind::#var, }
+    });
+    let this = if variants.is_empty() {
+        quote! { *self }
+    } else {
+        quote! { self }
+    };
+
+    let impls = enum_repr_impls(input, kind);
+    let code = wrap_in_const(
+        nl_path,
+        "ENUM_REPR",
+        ident,
+        quote! {
+            #[allow(dead_code)]
+            impl #impl_generics #ident #ty_generics #where_clause {
+                pub fn kind(&self) -> #kind {
+                    match #this {
+                        #(#arms)*
+                    }
+                }
+            }
+
+            #impls
+            #code
+        },
+    );
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #repr
+        #vis enum #kind {
+            #(#declare)*
+        }
+
+        #code
+    })
+}
+
+/// repr conversions, names and iteration of the unit-only enum `ident`
+/// declared with the variants of `input`
+fn enum_repr_impls(input: &Enum, ident: &Ident) -> TokenStream {
+    let variants = &input.variants;
+    let repr = input
+        .repr
+        .clone()
+        .unwrap_or_else(|| Ident::new("isize", Span::call_site()));
+    let names = variant_names(input);
+
+    // cfgs, variants and names of the variants kept by `skip`
+    let only = |skip: Option<Skip>| {
+        let mut kept = (Vec::new(), Vec::new(), Vec::new());
+        for (v, name) in variants.iter().zip(&names) {
+            if skip.is_some_and(|skip| input.skips(v, skip)) {
+                continue;
+            }
+            let cfgs = &v.cfgs;
+            kept.0.push(quote! { #(#cfgs)* });
+            kept.1.push(&v.ident);
+            kept.2.push(name);
+        }
+        kept
+    };
+
+    let (cfgs, vars, all_names) = only(None);
+    let (iter_cfgs, iter_vars, _) = only(Some(Skip::Iter));
+    let (name_cfgs, _, shown_names) = only(Some(Skip::Display));
+    // a reference to an empty enum is not matched by no arms
+    let this = if variants.is_empty() {
+        quote! { *self }
+    } else {
+        quote! { self }
+    };
+
+    let display = if variants.iter().all(|v| input.with(v).is_none()) {
+        quote! { f.pad(self.name()) }
+    } else {
+        let arms = variants.iter().map(|v| {
+            let (cfgs, var) = (&v.cfgs, &v.ident);
+            match input.with(v) {
+                Some(with) => quote! {
+                    #(#cfgs)* #ident::#var => {
+                        f.pad(&::std::borrow::Cow::<'static, str>::from(
+                            #with::to_str(self),
+                        ))
+                    },
+                },
+                None => {
+                    quote! { #(#cfgs)* #ident::#var => f.pad(self.name()), }
+                },
+            }
+        });
+        quote! {
+            match #this {
+                #(#arms)*
+            }
+        }
+    };
+    // as `Display`, the `&'static str` of `as_str` can't hold the formatted
+    // `with` names
+    let cow = if variants.iter().all(|v| input.with(v).is_none()) {
+        quote! { ::std::borrow::Cow::Borrowed(v.as_str()) }
+    } else {
+        let arms = variants.iter().map(|v| {
+            let (cfgs, var) = (&v.cfgs, &v.ident);
+            match input.with(v) {
+                Some(with) => quote! {
+                    #(#cfgs)* #ident::#var => {
+                        ::std::borrow::Cow::from(#with::to_str(&v))
+                    },
+                },
+                None => quote! {
+                    #(#cfgs)* #ident::#var => {
+                        ::std::borrow::Cow::Borrowed(v.as_str())
+                    },
+                },
+            }
+        });
+        quote! {
+            match v {
+                #(#arms)*
+            }
+        }
+    };
+    let (repr_cfgs, repr_vars, _) = only(Some(Skip::All));
+    let values = (0..repr_vars.len())
+        .map(|i| format_ident!("__VALUE_{}", i))
+        .collect::<Vec<_>>();
+    let parse = enum_repr_parse(input, ident, false);
+
+    quote! {
+        #[allow(dead_code)]
+        impl #ident {
+            /// variants in declaration order, without the `skip_iter` ones
+            pub const VARIANTS: &'static [#ident] =
+                &[#(#iter_cfgs #ident::#iter_vars,)*];
+
+            /// names in declaration order, without the `skip_display` ones
+            pub const NAMES: &'static [&'static str] =
+                &[#(#name_cfgs #shown_names,)*];
+
+            pub const COUNT: usize = Self::VARIANTS.len();
+
+            /// the name from the renamed name table, without allocating
+            pub const fn as_str(&self) -> &'static str {
+                match #this {
+                    #(#cfgs #ident::#vars => #all_names,)*
+                }
+            }
+
+            pub fn name(&self) -> &'static str {
+                self.as_str()
+            }
+
+            pub fn iter() -> impl ::std::iter::Iterator<Item = #ident> {
+                Self::VARIANTS.iter().map(|v| match *v {
+                    #(#cfgs #ident::#vars => #ident::#vars,)*
+                })
+            }
+        }
+
+        impl ::std::convert::From<#ident> for #repr {
+            fn from(v: #ident) -> Self {
+                v as #repr
+            }
+        }
+
+        impl ::std::convert::TryFrom<#repr> for #ident {
+            type Error = #repr;
+
+            fn try_from(v: #repr) -> ::std::result::Result<Self, #repr> {
+                #(#repr_cfgs const #values: #repr = #ident::#repr_vars as #repr;)*
+
+                match v {
+                    #(#repr_cfgs #values => ::std::result::Result::Ok(#ident::#repr_vars),)*
+                    _ => ::std::result::Result::Err(v),
+                }
+            }
+        }
+
+        impl ::std::fmt::Display for #ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                #display
+            }
+        }
+
+        impl ::std::convert::From<#ident> for &'static str {
+            fn from(v: #ident) -> Self {
+                v.as_str()
+            }
+        }
+
+        impl ::std::convert::From<#ident> for ::std::borrow::Cow<'static, str> {
+            fn from(v: #ident) -> Self {
+                #cow
+            }
+        }
+
+        impl ::std::convert::AsRef<str> for #ident {
+            fn as_ref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        #parse
+    }
+}
+
+/// names, `Display`, parsing and iteration of an enum with `flatten`
+/// variants, named by their own name, the separator and the nested name
+fn enum_repr_flatten(input: &Enum) -> TokenStream {
+    split!(input as ident, variants);
+    let names = variant_names(input);
+    let separator = input.separator();
+
+    let (mut count, mut iter, mut display) =
+        (Vec::new(), Vec::new(), Vec::new());
+    for (v, name) in variants.iter().zip(&names) {
+        let (cfgs, var) = (&v.cfgs, &v.ident);
+        let nested = match input.flatten(v) {
+            true => nested_ty(v),
+            false => None,
+        };
+
+        if !input.skips(v, Skip::Iter) {
+            count.push(match nested {
+                Some(ty) => quote! { #(#cfgs)* { n += <#ty>::COUNT; } },
+                None => quote! { #(#cfgs)* { n += 1; } },
+            });
+            iter.push(match nested {
+                Some(ty) => quote! {
+                    #(#cfgs)*
+                    let iter = iter.chain(<#ty>::iter().map(#ident::#var));
+                },
+                None => quote! {
+                    #(#cfgs)*
+                    let iter = iter.chain(::std::iter::once(#ident::#var));
+                },
+            });
+        }
+
+        display.push(match (nested, input.with(v)) {
+            (Some(_), _) => {
+                let prefix = format!("{}{}", name, separator);
+                quote! {
+                    #(#cfgs)* #ident::#var(v) => {
+                        f.write_str(#prefix)?;
+                        ::std::fmt::Display::fmt(v, f)
+                    },
+                }
+            },
+            (None, Some(with)) => quote! {
+                #(#cfgs)* #ident::#var => {
+                    f.pad(&::std::borrow::Cow::<'static, str>::from(
+                        #with::to_str(self),
+                    ))
+                },
+            },
+            (None, None) => quote! { #(#cfgs)* #ident::#var => f.pad(#name), },
+        });
+    }
+    let parse = enum_repr_parse(input, ident, true);
+
+    quote! {
+        #[allow(dead_code)]
+        impl #ident {
+            /// variants and nested variants, without the `skip_iter` ones
+            pub const COUNT: usize = {
+                let mut n = 0;
+                #(#count)*
+                n
+            };
+
+            /// variants in declaration order, the `flatten` ones replaced by
+            /// every nested variant, without the `skip_iter` ones
+            pub fn iter() -> impl ::std::iter::Iterator<Item = #ident> {
+                let iter = ::std::iter::empty();
+                #(#iter)*
+                iter
+            }
+        }
+
+        impl ::std::fmt::Display for #ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                match self {
+                    #(#display)*
+                }
+            }
+        }
+
+        impl ::std::convert::From<#ident> for ::std::borrow::Cow<'static, str> {
+            fn from(v: #ident) -> Self {
+                ::std::borrow::Cow::Owned(::std::string::ToString::to_string(&v))
+            }
+        }
+
+        #parse
+    }
+}
+
+/// `TryFrom<&str>`, `TryFrom<String>` and `FromStr` of `ident` trying the
+/// names, then the nested enums of `flatten` variants when `flatten`, then
+/// the `with` modules
+fn enum_repr_parse(input: &Enum, ident: &Ident, flatten: bool) -> TokenStream {
+    let unknown = format!("unknown variant of `{}`", ident);
+    let names = variant_names(input);
+    let separator = input.separator();
+
+    let (mut arms, mut nested) = (Vec::new(), Vec::new());
+    // variants with a `with` module are parsed by its `from_str` instead,
+    // which may only return the variants owning it and kept by `skip_parse`
+    let mut with = Vec::<(&Path, Vec<TokenStream>)>::new();
+    for (v, name) in input.variants.iter().zip(&names) {
+        if input.skips(v, Skip::Parse) {
+            continue;
+        }
+        let (cfgs, var) = (&v.cfgs, &v.ident);
+
+        if let Some(ty) = nested_ty(v).filter(|_| flatten && input.flatten(v)) {
+            let prefix = format!("{}{}", name, separator);
+            nested.push(quote! {
+                #(#cfgs)*
+                {
+                    if let ::std::option::Option::Some(rest) = s.strip_prefix(#prefix) {
+                        if let ::std::result::Result::Ok(v) =
+                            <#ty as ::std::convert::TryFrom<&str>>::try_from(rest)
+                        {
+                            return ::std::result::Result::Ok(#ident::#var(v));
+                        }
+                    }
+                }
+            });
+            continue;
+        }
+
+        match input.with(v) {
+            Some(path) => {
+                let owned = quote! {
+                    #(#cfgs)* #ident::#var => {
+                        return ::std::result::Result::Ok(v);
+                    },
+                };
+                match with.iter_mut().find(|(with, _)| *with == path) {
+                    Some((_, vars)) => vars.push(owned),
+                    None => with.push((path, vec![owned])),
+                }
+            },
+            None => arms.push(quote! {
+                #(#cfgs)* #name => {
+                    return ::std::result::Result::Ok(#ident::#var);
+                },
+            }),
+        }
+    }
+
+    let (with_paths, with_vars): (Vec<_>, Vec<_>) = with.into_iter().unzip();
+
+    quote! {
+        /// fails with the unmatched input
+        impl<'a> ::std::convert::TryFrom<&'a str> for #ident {
+            type Error = &'a str;
+
+            fn try_from(s: &'a str) -> ::std::result::Result<Self, &'a str> {
+                match s {
+                    #(#arms)*
+                    _ => {},
+                }
+
+                #(#nested)*
+
+                #(
+                    if let ::std::option::Option::Some(v) = #with_paths::from_str(s) {
+                        #[allow(unreachable_patterns)]
+                        match v {
+                            #(#with_vars)*
+                            _ => {},
+                        }
+                    }
+                )*
+
+                ::std::result::Result::Err(s)
+            }
+        }
+
+        /// fails with the unmatched input
+        impl ::std::convert::TryFrom<::std::string::String> for #ident {
+            type Error = ::std::string::String;
+
+            fn try_from(
+                s: ::std::string::String,
+            ) -> ::std::result::Result<Self, ::std::string::String> {
+                match <Self as ::std::convert::TryFrom<&str>>::try_from(&s).ok() {
+                    ::std::option::Option::Some(v) => ::std::result::Result::Ok(v),
+                    ::std::option::Option::None => ::std::result::Result::Err(s),
+                }
+            }
+        }
+
+        /// borrows `s` through `TryFrom<&str>`, the error is a static message
+        impl ::std::str::FromStr for #ident {
+            type Err = &'static str;
+
+            fn from_str(s: &str) -> ::std::result::Result<Self, &'static str> {
+                <Self as ::std::convert::TryFrom<&str>>::try_from(s)
+                    .map_err(|_| #unknown)
+            }
+        }
+    }
+}
+
+/// string names of the variants of `input`, skipped display variants are
+/// named by their identifier, so what `Display` writes still parses
+fn variant_names(input: &Enum) -> Vec<String> {
+    input
+        .variants
+        .iter()
+        .map(|v| match input.skips(v, Skip::Display) {
+            true => v.ident.to_string(),
+            false => variant_name(&input.attrs, &v.attrs, &v.ident),
+        })
+        .collect()
+}
+
+/// type of the single unnamed field of `var`
+fn nested_ty(var: &Variant) -> Option<&Type> {
+    match &var.fields {
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+            Some(&fields.unnamed[0].ty)
+        },
+        _ => None,
+    }
+}
+
+pub fn accessors(input: TokenStream) -> Result<TokenStream> {
+    let parsed = syn::parse2::<Accessors>(input.clone())?;
+    let output = _accessors(&parsed)?;
+
+    debug_expansion("accessors", &parsed.ident, &input, &parsed, &output);
+    Ok(output)
+}
+
+fn _accessors(input: &Accessors) -> Result<TokenStream> {
+    split!(input as ident, generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let methods = input.fields.iter().map(|field| {
+        let vis = input.vis(field);
+        let name = input.name(field);
+        let (field_ident, ty) = (&field.ident, &field.ty);
+
+        let getter = match input.getter(field) {
+            Getter::Ref => quote! {
+                #vis fn #name(&self) -> &#ty { &self.#field_ident }
+            },
+            Getter::Copy => quote! {
+                #vis fn #name(&self) -> #ty { self.#field_ident }
+            },
+            Getter::Clone => quote! {
+                #vis fn #name(&self) -> #ty {
+                    ::std::clone::Clone::clone(&self.#field_ident)
+                }
+            },
+            Getter::Skip => quote! {},
+        };
+
+        let setter = input.setter(field);
+        let (set, with) = match setter {
+            Setter::All => (true, true),
+            Setter::Set => (true, false),
+            Setter::With => (false, true),
+            Setter::Skip => (false, false),
+        };
+
+        let set = if set {
+            let mut_ = format_ident!("{}_mut", name);
+            let set_ = format_ident!("set_{}", name);
+            quote! {
+                #vis fn #mut_(&mut self) -> &mut #ty { &mut self.#field_ident }
+
+                #vis fn #set_(&mut self, value: #ty) -> &mut Self {
+                    self.#field_ident = value;
+                    self
+                }
+            }
+        } else {
+            quote! {}
+        };
+
+        let with = if with {
+            let with_ = format_ident!("with_{}", name);
+            quote! {
+                #vis fn #with_(mut self, value: #ty) -> Self {
+                    self.#field_ident = value;
+                    self
+                }
+            }
+        } else {
+            quote! {}
+        };
+
+        quote! { #getter #set #with }
+    });
+
+    // inherent impl, nothing from `_derive` is needed so no const wrapper
+    Ok(quote! {
+        #[allow(dead_code)]
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#methods)*
+        }
+    })
+}
+
+pub fn newtype(input: TokenStream) -> Result<TokenStream> {
+    let parsed = syn::parse2::<Newtype>(input.clone())?;
+    let output = _newtype(&parsed)?;
+
+    debug_expansion("newtype", &parsed.ident, &input, &parsed, &output);
+    Ok(output)
+}
+
+fn _newtype(input: &Newtype) -> Result<TokenStream> {
+    split!(input as ident, generics, member, ty, markers);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let this = quote! { #ident #ty_generics };
+
+    let build = quote! {
+        Self {
+            #member: value,
+            #(#markers: ::std::marker::PhantomData,)*
+        }
+    };
+
+    let mut code = Vec::new();
+
+    // construction, checked by the validator when there is one
+    match input.validate() {
+        Some(validate) => {
+            let error = input.error();
+            code.push(quote! {
+                impl #impl_generics #this #where_clause {
+                    pub fn new(value: #ty) -> ::std::result::Result<Self, #error> {
+                        #validate(&value)?;
+                        ::std::result::Result::Ok(#build)
+                    }
+                }
+
+                impl #impl_generics ::std::convert::TryFrom<#ty> for #this #where_clause {
+                    type Error = #error;
+
+                    fn try_from(value: #ty) -> ::std::result::Result<Self, #error> {
+                        Self::new(value)
+                    }
+                }
+            });
+        },
+        None => code.push(quote! {
+            impl #impl_generics #this #where_clause {
+                pub fn new(value: #ty) -> Self {
+                    #build
+                }
+            }
+
+            impl #impl_generics ::std::convert::From<#ty> for #this #where_clause {
+                fn from(value: #ty) -> Self {
+                    #build
+                }
+            }
+        }),
+    }
+
+    code.push(quote! {
+        impl #impl_generics #this #where_clause {
+            pub fn into_inner(self) -> #ty {
+                self.#member
+            }
+        }
+
+        impl #impl_generics ::std::convert::AsRef<#ty> for #this #where_clause {
+            fn as_ref(&self) -> &#ty {
+                &self.#member
+            }
+        }
+
+        impl #impl_generics ::std::borrow::Borrow<#ty> for #this #where_clause {
+            fn borrow(&self) -> &#ty {
+                &self.#member
+            }
+        }
+    });
+
+    if !input.inner_is_param() {
+        code.push(quote! {
+            impl #impl_generics ::std::convert::From<#this> for #ty #where_clause {
+                fn from(value: #this) -> Self {
+                    value.#member
+                }
+            }
+        });
+    }
+
+    if input.deref() {
+        code.push(quote! {
+            impl #impl_generics ::std::ops::Deref for #this #where_clause {
+                type Target = #ty;
+
+                fn deref(&self) -> &#ty {
+                    &self.#member
+                }
+            }
+        });
+
+        // mutation would bypass the validator
+        if input.validate().is_none() {
+            code.push(quote! {
+                impl #impl_generics ::std::ops::DerefMut for #this #where_clause {
+                    fn deref_mut(&mut self) -> &mut #ty {
+                        &mut self.#member
+                    }
+                }
+            });
+        }
+    }
+
+    if input.transparent() {
+        let (err, parse) = match input.validate() {
+            Some(_) => {
+                let error = input.error();
+                (
+                    quote! { #error },
+                    quote! {
+                        let value = s.parse::<#ty>().map_err(|e| {
+                            <#error as ::std::convert::From<_>>::from(
+                                ::std::string::ToString::to_string(&e),
+                            )
+                        })?;
+                        Self::new(value)
+                    },
+                )
+            },
+            None => (
+                quote! { <#ty as ::std::str::FromStr>::Err },
+                quote! { s.parse::<#ty>().map(Self::new) },
+            ),
+        };
+
+        code.push(quote! {
+            impl #impl_generics ::std::fmt::Display for #this #where_clause {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    ::std::fmt::Display::fmt(&self.#member, f)
+                }
+            }
+
+            impl #impl_generics ::std::str::FromStr for #this #where_clause {
+                type Err = #err;
+
+                fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                    #parse
+                }
+            }
+        });
+    }
+
+    Ok(quote! { #(#code)* })
+}
+
+pub fn tagged(input: TokenStream) -> Result<TokenStream> {
+    let parsed = syn::parse2::<Tagged>(input.clone())?;
+    let output = _tagged(&parsed)?;
+
+    debug_expansion("tagged", &parsed.ident, &input, &parsed, &output);
+    Ok(output)
+}
+
+fn _tagged(input: &Tagged) -> Result<TokenStream> {
+    split!(input as ident, generics, tag);
+    let name = ident.to_string();
+
+    // type parameters are encoded through the same traits
+    let mut encode_generics = generics.clone();
+    let mut decode_generics = generics.clone();
+    for param in generics.type_params() {
+        let param = &param.ident;
+        encode_generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(#param: _derive::Encode));
+        decode_generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(#param: _derive::Decode));
+    }
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let (encode_impl, _, encode_where) = encode_generics.split_for_impl();
+    let (decode_impl, _, decode_where) = decode_generics.split_for_impl();
+
+    let (encode, decode) = match &input.body {
+        TaggedBody::Struct(fields) => {
+            let (pattern, build) = tagged_fields(fields);
+            let encode = tagged_payload_encode(input, fields);
+            let decode = tagged_payload_decode(input, quote! { Self #build });
+            (
+                quote! {
+                    let Self #pattern = self;
+                    #encode
+                    ::std::result::Result::Ok(())
+                },
+                decode,
+            )
+        },
+        TaggedBody::Enum(variants) => {
+            // tags follow the discriminants, counting up from the last one
+            let tags = variants
+                .iter()
+                .enumerate()
+                .map(|(i, var)| {
+                    let name = format_ident!("__TAG_{}", i);
+                    let value = match (&var.discriminant, i) {
+                        (Some(expr), _) => quote! { (#expr) as #tag },
+                        (None, 0) => quote! { 0 },
+                        (None, _) => {
+                            let prev = format_ident!("__TAG_{}", i - 1);
+                            quote! { #prev + 1 }
+                        },
+                    };
+                    (name, value)
+                })
+                .collect::<Vec<_>>();
+            let consts = tags.iter().map(|(name, value)| {
+                quote! { const #name: #tag = #value; }
+            });
+            let consts = quote! { #(#consts)* };
+
+            let encode_arms =
+                variants.iter().zip(&tags).map(|(var, (t, _))| {
+                    let var_ident = &var.ident;
+                    let (pattern, _) = tagged_fields(&var.fields);
+                    let encode = tagged_payload_encode(input, &var.fields);
+                    quote! {
+                        #ident::#var_ident #pattern => {
+                            _derive::Encode::encode(&#t, out)?;
+                            #encode
+                            ::std::result::Result::Ok(())
+                        }
+                    }
+                });
+            let decode_arms =
+                variants.iter().zip(&tags).map(|(var, (t, _))| {
+                    let var_ident = &var.ident;
+                    let (_, build) = tagged_fields(&var.fields);
+                    let decode = tagged_payload_decode(
+                        input,
+                        quote! { #ident::#var_ident #build },
+                    );
+                    quote! { #t => #decode, }
+                });
+
+            // a reference to an empty enum is not matched by no arms
+            let this = if variants.is_empty() {
+                quote! { *self }
+            } else {
+                quote! { self }
+            };
+
+            (
+                quote! {
+                    #consts
+                    match #this {
+                        #(#encode_arms)*
+                    }
+                },
+                quote! {
+                    #consts
+                    let tag = <#tag as _derive::Decode>::decode(input)?;
+                    match tag {
+                        #(#decode_arms)*
+                        _ => ::std::result::Result::Err(
+                            _derive::DecodeError::UnknownTag {
+                                ty: #name,
+                                tag: ::std::convert::TryFrom::try_from(tag).ok(),
+                            },
+                        ),
+                    }
+                },
+            )
+        },
+    };
+
+    let code = quote! {
+        impl #encode_impl _derive::Encode for #ident #ty_generics #encode_where {
+            #[allow(unused_variables)]
+            fn encode(
+                &self,
+                out: &mut ::std::vec::Vec<u8>,
+            ) -> ::std::result::Result<(), _derive::EncodeError> {
+                #encode
+            }
+        }
+
+        impl #decode_impl _derive::Decode for #ident #ty_generics #decode_where {
+            #[allow(unused_variables)]
+            fn decode(
+                input: &mut &[u8],
+            ) -> ::std::result::Result<Self, _derive::DecodeError> {
+                #decode
+            }
+        }
+    };
+
+    Ok(wrap_in_const(
+        Some(&input.codec()),
+        "TAGGED",
+        &input.ident,
+        code,
+    ))
+}
+
+/// pattern binding the fields to `__0`, `__1`, .. and the constructor
+/// decoding them in order
+fn tagged_fields(fields: &Fields) -> (TokenStream, TokenStream) {
+    let binds = (0..fields.len()).map(|i| format_ident!("__{}", i));
+    let decode = fields.iter().map(|_| {
+        quote! { _derive::Decode::decode(input)? }
+    });
+
+    match fields {
+        Fields::Named(named) => {
+            let names =
+                named.named.iter().map(|f| &f.ident).collect::<Vec<_>>();
+            (
+                quote! { { #(#names: #binds),* } },
+                quote! { { #(#names: #decode),* } },
+            )
+        },
+        Fields::Unnamed(_) => {
+            (quote! { (#(#binds),*) }, quote! { (#(#decode),*) })
+        },
+        Fields::Unit => (quote! {}, quote! {}),
+    }
+}
+
+fn tagged_payload_encode(input: &Tagged, fields: &Fields) -> TokenStream {
+    let binds = (0..fields.len()).map(|i| format_ident!("__{}", i));
+    let encode = quote! { #(_derive::Encode::encode(#binds, out)?;)* };
+
+    match input.content() {
+        Some(len) => quote! {
+            _derive::encode_content::<#len, _>(out, |out| {
+                #encode
+                ::std::result::Result::Ok(())
+            })?;
+        },
+        None => encode,
+    }
 }
 
-/*
-_match_discrs(input: &DeriveInput) -> Result<TokenStream> {
-    let fields = input.variants.iter()
-        .filter(|v| !v.
+fn tagged_payload_decode(input: &Tagged, build: TokenStream) -> TokenStream {
+    match input.content() {
+        Some(len) => quote! {
+            _derive::decode_content::<#len, _, _>(input, |input| {
+                ::std::result::Result::Ok(#build)
+            })
+        },
+        None => quote! { ::std::result::Result::Ok(#build) },
+    }
 }
-*/
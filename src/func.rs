@@ -1,47 +1,1538 @@
-use ast::{lit_from_token, EnumMacro};
+use ast::{
+    deny_unknown_fields, get_container_attrs, get_variant_attrs, repr_bits,
+    variant_name, CodecMacro, ConvMacro, EnumMacro, EnumReprAttr, EnumsMacro,
+    ForwardImpl, ForwardItem, ForwardMacro, ForwardOp, ForwardOps,
+    ForwardTrait, ImplMany, ImplManyMacro, VariantAttr,
+};
+use attr::forward_body;
+use derive;
+use pretty::format_tokens;
 use proc_macro2::TokenStream;
-use syn::*;
+use quote::ToTokens;
+use std::collections::HashSet;
+use syn::{spanned::Spanned, *};
+use util::debug_expansion;
 
-pub fn forward_impl(input: TokenStream) -> Result<TokenStream> {
-    dump!(input);
+pub fn forward_impl(tokens: TokenStream) -> Result<TokenStream> {
+    let input = syn::parse2::<ForwardMacro>(tokens.clone())?;
 
-    Ok(quote! { #input })
+    let impls = input
+        .impls
+        .iter()
+        .flat_map(|imp| {
+            imp.traits.iter().map(move |item| match item {
+                ForwardItem::Trait(t) => forward_trait(imp, t),
+                ForwardItem::Ops(ops) => forward_ops(imp, ops),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let output = quote! { #(#impls)* };
+    let name = input.impls.first().map(|imp| imp.ty.to_token_stream());
+    debug_expansion(
+        "forward_impl",
+        &name.unwrap_or_default(),
+        &tokens,
+        &input,
+        &output,
+    );
+
+    Ok(output)
 }
 
-pub fn make_enum(input: TokenStream) -> Result<TokenStream> {
-    let input = syn::parse2::<EnumMacro>(input)?;
-    split!(input as attrs, vis, ident, repr, default, variants);
+/// traits taking a type argument, `AsRef<str>`, defaulting to the field type
+const FORWARD_GENERIC: &[&str] =
+    &["AsRef", "AsMut", "Borrow", "BorrowMut", "From"];
 
-    let default = lit_from_token(&default)?;
+fn forward_trait(input: &ForwardImpl, t: &ForwardTrait) -> Result<TokenStream> {
+    split!(input as generics, ty, member);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
 
-    let declare_variants = variants.iter().map(|v| {
-        split!(v as attrs, ident, fields);
+    if t.brace_token.is_some() {
+        return forward_items(input, t);
+    }
+
+    let last = t.path.segments.last().expect("non empty trait path");
+    let name = &last.ident;
+    let known = name.to_string();
+
+    let arg = match &last.arguments {
+        PathArguments::None => None,
+        PathArguments::AngleBracketed(args)
+            if args.args.len() == 1
+                && FORWARD_GENERIC.contains(&known.as_str()) =>
+        {
+            match &args.args[0] {
+                GenericArgument::Type(arg) => Some(arg),
+                _ => return err!(name: "`{}` expects a type argument", name),
+            }
+        },
+        _ => return err!(name: "unexpected arguments on `{}`", name),
+    };
+
+    let inner = || {
+        input.inner().ok_or_else(|| {
+            Error::new(
+                name.span(),
+                format!(
+                    "forwarding `{}` needs the field type, write `=> {}: Type`",
+                    name,
+                    member.to_token_stream()
+                ),
+            )
+        })
+    };
+
+    let (trait_, body) = match known.as_str() {
+        "Display" | "Debug" | "LowerHex" | "UpperHex" | "Octal" | "Binary"
+        | "LowerExp" | "UpperExp" | "Pointer" => (
+            quote! { ::std::fmt::#name },
+            quote! {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    ::std::fmt::#name::fmt(&self.#member, f)
+                }
+            },
+        ),
+        "Hash" => (
+            quote! { ::std::hash::Hash },
+            quote! {
+                fn hash<__H: ::std::hash::Hasher>(&self, state: &mut __H) {
+                    ::std::hash::Hash::hash(&self.#member, state)
+                }
+            },
+        ),
+        "PartialEq" => (
+            quote! { ::std::cmp::PartialEq },
+            quote! {
+                fn eq(&self, other: &Self) -> bool {
+                    self.#member == other.#member
+                }
+            },
+        ),
+        "Eq" => (quote! { ::std::cmp::Eq }, quote! {}),
+        "PartialOrd" => (
+            quote! { ::std::cmp::PartialOrd },
+            quote! {
+                fn partial_cmp(
+                    &self,
+                    other: &Self,
+                ) -> ::std::option::Option<::std::cmp::Ordering> {
+                    ::std::cmp::PartialOrd::partial_cmp(&self.#member, &other.#member)
+                }
+            },
+        ),
+        "Ord" => (
+            quote! { ::std::cmp::Ord },
+            quote! {
+                fn cmp(&self, other: &Self) -> ::std::cmp::Ordering {
+                    ::std::cmp::Ord::cmp(&self.#member, &other.#member)
+                }
+            },
+        ),
+        "Clone" => (
+            quote! { ::std::clone::Clone },
+            quote! {
+                fn clone(&self) -> Self {
+                    Self { #member: ::std::clone::Clone::clone(&self.#member) }
+                }
+            },
+        ),
+        "Default" => (
+            quote! { ::std::default::Default },
+            quote! {
+                fn default() -> Self {
+                    Self { #member: ::std::default::Default::default() }
+                }
+            },
+        ),
+        // without the field type its error is boxed, the type is inferred;
+        // `dyn (..)` keeps 2015 crates from reading `dyn ::std` as a path
+        "FromStr" => match input.inner() {
+            Some(inner) => (
+                quote! { ::std::str::FromStr },
+                quote! {
+                    type Err = <#inner as ::std::str::FromStr>::Err;
+
+                    fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                        <#inner as ::std::str::FromStr>::from_str(s)
+                            .map(|v| Self { #member: v })
+                    }
+                },
+            ),
+            None => (
+                quote! { ::std::str::FromStr },
+                quote! {
+                    type Err = ::std::boxed::Box<
+                        dyn (::std::error::Error) + ::std::marker::Send + ::std::marker::Sync
+                    >;
+
+                    fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                        <_ as ::std::str::FromStr>::from_str(s)
+                            .map(|v| Self { #member: v })
+                            .map_err(::std::convert::Into::into)
+                    }
+                },
+            ),
+        },
+        "From" => match arg {
+            Some(arg) => (
+                quote! { ::std::convert::From<#arg> },
+                quote! {
+                    fn from(v: #arg) -> Self {
+                        Self { #member: ::std::convert::From::from(v) }
+                    }
+                },
+            ),
+            None => {
+                let inner = inner()?;
+                (
+                    quote! { ::std::convert::From<#inner> },
+                    quote! {
+                        fn from(v: #inner) -> Self { Self { #member: v } }
+                    },
+                )
+            },
+        },
+        "AsRef" | "Borrow" => {
+            let (module, method) = match known.as_str() {
+                "AsRef" => (quote! { convert }, quote! { as_ref }),
+                _ => (quote! { borrow }, quote! { borrow }),
+            };
+            let (target, get) = match arg {
+                Some(arg) => (
+                    arg.clone(),
+                    quote! { ::std::#module::#name::#method(&self.#member) },
+                ),
+                None => (inner()?.clone(), quote! { &self.#member }),
+            };
+            (
+                quote! { ::std::#module::#name<#target> },
+                quote! { fn #method(&self) -> &#target { #get } },
+            )
+        },
+        "AsMut" | "BorrowMut" => {
+            let (module, method) = match known.as_str() {
+                "AsMut" => (quote! { convert }, quote! { as_mut }),
+                _ => (quote! { borrow }, quote! { borrow_mut }),
+            };
+            let (target, get) = match arg {
+                Some(arg) => (
+                    arg.clone(),
+                    quote! { ::std::#module::#name::#method(&mut self.#member) },
+                ),
+                None => (inner()?.clone(), quote! { &mut self.#member }),
+            };
+            (
+                quote! { ::std::#module::#name<#target> },
+                quote! { fn #method(&mut self) -> &mut #target { #get } },
+            )
+        },
+        "Deref" => {
+            let inner = inner()?;
+            (
+                quote! { ::std::ops::Deref },
+                quote! {
+                    type Target = #inner;
+                    fn deref(&self) -> &Self::Target { &self.#member }
+                },
+            )
+        },
+        "DerefMut" => (
+            quote! { ::std::ops::DerefMut },
+            quote! {
+                fn deref_mut(&mut self) -> &mut Self::Target { &mut self.#member }
+            },
+        ),
+        _ => {
+            return err!(
+                name: "`{}` is not a built-in forwarded trait, list the items \
+                       to forward: `impl {} {{ fn .. ; }} for ..`",
+                name,
+                name
+            )
+        },
+    };
+
+    Ok(quote! {
+        impl #impl_generics #trait_ for #ty #where_clause {
+            #body
+        }
+    })
+}
+
+/// `(trait, method, operator)` of the binary operators
+const FORWARD_BINARY: &[(&str, &str, &str)] = &[
+    ("Add", "add", "+"),
+    ("Sub", "sub", "-"),
+    ("Mul", "mul", "*"),
+    ("Div", "div", "/"),
+    ("Rem", "rem", "%"),
+    ("BitAnd", "bitand", "&"),
+    ("BitOr", "bitor", "|"),
+    ("BitXor", "bitxor", "^"),
+    ("Shl", "shl", "<<"),
+    ("Shr", "shr", ">>"),
+];
+
+/// field methods reachable through `checked(..)`, `saturating(..)` and
+/// `wrapping(..)`, `neg` is the only unary one and `saturating_neg` only
+/// exists on signed integers
+const FORWARD_INHERENT: &[(&str, &[&str])] = &[
+    ("checked", &["add", "sub", "mul", "div", "rem", "neg"]),
+    ("saturating", &["add", "sub", "mul", "div", "neg"]),
+    ("wrapping", &["add", "sub", "mul", "div", "rem", "neg"]),
+];
+
+const UNSIGNED: &[&str] = &["u8", "u16", "u32", "u64", "u128", "usize"];
+
+fn forward_ops(input: &ForwardImpl, ops: &ForwardOps) -> Result<TokenStream> {
+    split!(input as generics, ty, member);
+    let ctor = forward_ctor(ty)?;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    // impls on references borrow for `'__a`
+    let mut ref_generics = generics.clone();
+    ref_generics.params.insert(0, parse_quote!('__a));
+    let (ref_generics, _, _) = ref_generics.split_for_impl();
+
+    let mut impls = Vec::new();
+    let mut inherent = Vec::new();
+
+    for op in &ops.ops {
+        let path = match op {
+            ForwardOp::Trait(path) => path,
+            ForwardOp::Inherent { kind, methods, .. } => {
+                let known = FORWARD_INHERENT
+                    .iter()
+                    .find(|(k, _)| kind == k)
+                    .map_or(&[][..], |(_, methods)| methods);
+                let unsigned = match &input.inner {
+                    Some((_, Type::Path(p))) => {
+                        UNSIGNED.iter().any(|u| p.path.is_ident(u))
+                    },
+                    _ => false,
+                };
+
+                for method in methods {
+                    if !known.iter().any(|m| method == m) {
+                        return err!(
+                            method: "`{}` has no `{}`, expected one of {}{}",
+                            kind,
+                            method,
+                            known.join(", "),
+                            match kind == "saturating" {
+                                true => ", `neg` on signed fields only",
+                                false => "",
+                            }
+                        );
+                    }
+                    if kind == "saturating" && method == "neg" && unsigned {
+                        return err!(
+                            method: "`saturating_neg` only exists on signed \
+                                     integers, the field is unsigned"
+                        );
+                    }
+
+                    let name = format_ident!("{}_{}", kind, method);
+                    let (args, rhs) = match method.to_string().as_str() {
+                        "neg" => (quote! {}, quote! {}),
+                        _ => (quote! { rhs: #ty }, quote! { rhs.#member }),
+                    };
+
+                    inherent.push(if kind == "checked" {
+                        quote! {
+                            pub fn #name(self, #args) -> ::std::option::Option<#ty> {
+                                self.#member
+                                    .#name(#rhs)
+                                    .map(|v| #ctor { #member: v })
+                            }
+                        }
+                    } else {
+                        quote! {
+                            pub fn #name(self, #args) -> #ty {
+                                #ctor { #member: self.#member.#name(#rhs) }
+                            }
+                        }
+                    });
+                }
+                continue;
+            },
+        };
+
+        let last = path.segments.last().expect("non empty trait path");
+        let name = &last.ident;
+        let known = name.to_string();
+        let arg = match &last.arguments {
+            PathArguments::None => None,
+            PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+                match &args.args[0] {
+                    GenericArgument::Type(arg) => Some(arg),
+                    _ => {
+                        return err!(name: "`{}` expects a type argument", name)
+                    },
+                }
+            },
+            _ => return err!(name: "unexpected arguments on `{}`", name),
+        };
+
+        // right hand side type, and its value when owned or borrowed
+        let (rhs, owned, borrowed) = match arg {
+            Some(arg) => (arg, quote! { rhs }, quote! { rhs }),
+            None => (ty, quote! { rhs.#member }, quote! { &rhs.#member }),
+        };
+
+        let binary = FORWARD_BINARY.iter().find(|(t, ..)| *t == known);
+        let assign = FORWARD_BINARY
+            .iter()
+            .find(|(t, ..)| format!("{}Assign", t) == known);
+
+        if let Some((_, method, op)) = binary {
+            let method = Ident::new(method, name.span());
+            let op = syn::parse_str::<BinOp>(op)?;
+
+            let combos = vec![
+                (
+                    quote! { #impl_generics },
+                    quote! { #ty },
+                    quote! { self.#member },
+                    quote! { #rhs },
+                    &owned,
+                ),
+                (
+                    quote! { #ref_generics },
+                    quote! { #ty },
+                    quote! { self.#member },
+                    quote! { &'__a #rhs },
+                    &borrowed,
+                ),
+                (
+                    quote! { #ref_generics },
+                    quote! { &'__a #ty },
+                    quote! { &self.#member },
+                    quote! { #rhs },
+                    &owned,
+                ),
+                (
+                    quote! { #ref_generics },
+                    quote! { &'__a #ty },
+                    quote! { &self.#member },
+                    quote! { &'__a #rhs },
+                    &borrowed,
+                ),
+            ];
+
+            for (generics, self_ty, lhs, rhs_ty, rhs) in combos {
+                impls.push(quote! {
+                    impl #generics ::std::ops::#name<#rhs_ty> for #self_ty #where_clause {
+                        type Output = #ty;
+
+                        fn #method(self, rhs: #rhs_ty) -> #ty {
+                            #ctor { #member: #lhs #op #rhs }
+                        }
+                    }
+                });
+            }
+        } else if let Some((_, method, op)) = assign {
+            let method = format_ident!("{}_assign", method);
+            let op = syn::parse_str::<BinOp>(&format!("{}=", op))?;
+
+            impls.push(quote! {
+                impl #impl_generics ::std::ops::#name<#rhs> for #ty #where_clause {
+                    fn #method(&mut self, rhs: #rhs) {
+                        self.#member #op #owned;
+                    }
+                }
+
+                impl #ref_generics ::std::ops::#name<&'__a #rhs> for #ty #where_clause {
+                    fn #method(&mut self, rhs: &'__a #rhs) {
+                        self.#member #op #borrowed;
+                    }
+                }
+            });
+        } else {
+            let (module, method, op) = match known.as_str() {
+                "Neg" => (quote! { ops }, quote! { neg }, quote! { - }),
+                "Not" => (quote! { ops }, quote! { not }, quote! { ! }),
+                "Sum" => (quote! { iter }, quote! { sum }, quote! {}),
+                "Product" => (quote! { iter }, quote! { product }, quote! {}),
+                _ => {
+                    return err!(
+                        name: "unknown operator `{}`, expected one of {}, \
+                               their `Assign` forms, `Neg`, `Not`, `Sum`, \
+                               `Product`, `checked(..)`, `saturating(..)` or \
+                               `wrapping(..)`",
+                        name,
+                        FORWARD_BINARY
+                            .iter()
+                            .map(|(t, ..)| *t)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                },
+            };
+
+            if let Some(arg) = arg {
+                return err!(arg: "`{}` takes no right hand side type", name);
+            }
+
+            impls.push(if op.is_empty() {
+                quote! {
+                    impl #impl_generics ::std::#module::#name for #ty #where_clause {
+                        fn #method<__I>(iter: __I) -> #ty
+                        where
+                            __I: ::std::iter::Iterator<Item = #ty>,
+                        {
+                            #ctor { #member: iter.map(|v| v.#member).#method() }
+                        }
+                    }
+
+                    impl #ref_generics ::std::#module::#name<&'__a #ty> for #ty #where_clause {
+                        fn #method<__I>(iter: __I) -> #ty
+                        where
+                            __I: ::std::iter::Iterator<Item = &'__a #ty>,
+                        {
+                            #ctor { #member: iter.map(|v| &v.#member).#method() }
+                        }
+                    }
+                }
+            } else {
+                quote! {
+                    impl #impl_generics ::std::#module::#name for #ty #where_clause {
+                        type Output = #ty;
+
+                        fn #method(self) -> #ty {
+                            #ctor { #member: #op self.#member }
+                        }
+                    }
+
+                    impl #ref_generics ::std::#module::#name for &'__a #ty #where_clause {
+                        type Output = #ty;
+
+                        fn #method(self) -> #ty {
+                            #ctor { #member: #op &self.#member }
+                        }
+                    }
+                }
+            });
+        }
+    }
+
+    if !inherent.is_empty() {
+        impls.push(quote! {
+            impl #impl_generics #ty #where_clause {
+                #(#inherent)*
+            }
+        });
+    }
+
+    Ok(quote! { #(#impls)* })
+}
+
+/// path building the newtype in expressions, `Name::<T>` for `Name<T>`
+fn forward_ctor(ty: &Type) -> Result<Path> {
+    let mut path = match ty {
+        Type::Path(TypePath { qself: None, path }) => path.clone(),
+        _ => {
+            return err!(
+                ty: "operators need a named newtype, got `{}`",
+                ty.to_token_stream()
+            )
+        },
+    };
+
+    for segment in path.segments.iter_mut() {
+        if let PathArguments::AngleBracketed(args) = &mut segment.arguments {
+            args.colon2_token = Some(Default::default());
+        }
+    }
+
+    Ok(path)
+}
+
+/// `impl Trait { items } for ..`: bodiless methods call the field, default
+/// bodies are kept, associated types and consts come from the field type
+fn forward_items(input: &ForwardImpl, t: &ForwardTrait) -> Result<TokenStream> {
+    split!(input as generics, ty, member);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let path = &t.path;
+
+    // arguments are left to inference, `Trait<T>::m` is not an expression
+    let mut call_path = path.clone();
+    if let Some(last) = call_path.segments.last_mut() {
+        last.arguments = PathArguments::None;
+    }
+
+    let inner = |ident: &Ident| {
+        input.inner().ok_or_else(|| {
+            Error::new(
+                ident.span(),
+                format!(
+                    "forwarding `{}` needs the field type, write `=> {}: Type`",
+                    ident,
+                    member.to_token_stream()
+                ),
+            )
+        })
+    };
+
+    let items = t
+        .items
+        .iter()
+        .map(|item| match item {
+            TraitItem::Method(m) => {
+                let (attrs, sig) = (&m.attrs, &m.sig);
+                match &m.default {
+                    Some(block) => Ok(quote! { #(#attrs)* #sig #block }),
+                    None => {
+                        let mut method: ImplItemMethod =
+                            parse_quote!(#(#attrs)* #sig {});
+                        forward_body(&mut method, member, Some(&call_path))?;
+                        Ok(method.into_token_stream())
+                    },
+                }
+            },
+            TraitItem::Type(t) => {
+                let (attrs, ident) = (&t.attrs, &t.ident);
+                let value = match &t.default {
+                    Some((_, value)) => value.into_token_stream(),
+                    None => {
+                        let inner = inner(ident)?;
+                        quote! { <#inner as #path>::#ident }
+                    },
+                };
+                Ok(quote! { #(#attrs)* type #ident = #value; })
+            },
+            TraitItem::Const(c) => {
+                let (attrs, ident, ty) = (&c.attrs, &c.ident, &c.ty);
+                let value = match &c.default {
+                    Some((_, value)) => value.into_token_stream(),
+                    None => {
+                        let inner = inner(ident)?;
+                        quote! { <#inner as #path>::#ident }
+                    },
+                };
+                Ok(quote! { #(#attrs)* const #ident: #ty = #value; })
+            },
+            item => err!(item: "cannot forward `{}`", item.to_token_stream()),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        impl #impl_generics #path for #ty #where_clause {
+            #(#items)*
+        }
+    })
+}
+
+pub fn impl_many(tokens: TokenStream) -> Result<TokenStream> {
+    let input = syn::parse2::<ImplManyMacro>(tokens.clone())?;
+
+    let impls = input
+        .impls
+        .iter()
+        .map(impl_many_one)
+        .collect::<Result<Vec<_>>>()?;
+
+    let output = quote! { #(#impls)* };
+    let name = input.impls.first().map(|imp| imp.traits.to_token_stream());
+    debug_expansion(
+        "impl_many",
+        &name.unwrap_or_default(),
+        &tokens,
+        &input,
+        &output,
+    );
+
+    Ok(output)
+}
+
+fn impl_many_one(input: &ImplMany) -> Result<TokenStream> {
+    split!(input as attrs, generics, traits, vis, types);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    let has_items = input.brace_token.is_some()
+        || types.iter().any(|ty| ty.brace_token.is_some());
+    if has_items && traits.len() > 1 {
+        return err!(
+            "associated items need a single trait, got `{}`",
+            traits.to_token_stream()
+        );
+    }
+
+    if input.struct_token.is_none() && *vis != Visibility::Inherited {
+        return err!(vis: "visibility is only allowed on generated structs");
+    }
+
+    // attributes go to the structs, `cfg` to the impls as well
+    let (struct_attrs, impl_attrs) = match input.struct_token {
+        Some(_) => (attrs.iter().collect(), take_cfg!(attrs).collect()),
+        None => (Vec::new(), attrs.iter().collect::<Vec<_>>()),
+    };
+
+    let mut output = Vec::new();
+    for ty in types {
+        if input.struct_token.is_some() {
+            let ident = match &ty.ty {
+                Type::Path(TypePath { qself: None, path }) => path.get_ident(),
+                _ => None,
+            };
+            let ident = match ident {
+                Some(ident) => ident,
+                None => {
+                    let ty = &ty.ty;
+                    return err!(ty: "expected a unit struct name");
+                },
+            };
+
+            output.push(quote! {
+                #(#struct_attrs)*
+                #vis struct #ident;
+            });
+        }
+
+        let items = input.items_for(ty);
+        let ty = &ty.ty;
+        for trait_ in traits {
+            output.push(quote! {
+                #(#impl_attrs)*
+                impl #impl_generics #trait_ for #ty #where_clause {
+                    #(#items)*
+                }
+            });
+        }
+    }
+
+    Ok(quote! { #(#output)* })
+}
+
+/// runtime items of `#[derive(Tagged)]`, generated into the calling crate
+/// since a proc macro crate exports nothing else
+pub fn codec(tokens: TokenStream) -> Result<TokenStream> {
+    let input = syn::parse2::<CodecMacro>(tokens.clone())?;
+    split!(input as vis, ident);
+
+    let ints = [
+        "u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128",
+    ]
+    .iter()
+    .map(|ty| format_ident!("{}", ty));
+
+    let output = quote! {
+        #vis mod #ident {
+            #[derive(Debug, Clone, PartialEq, Eq)]
+            pub enum DecodeError {
+                /// the input ended `needed` bytes too early
+                UnexpectedEnd { needed: usize },
+                /// the tag matches no variant of `ty`, `None` for a `u128` tag
+                /// above `i128::MAX`
+                UnknownTag {
+                    ty: &'static str,
+                    tag: ::std::option::Option<i128>,
+                },
+                /// the payload decoded from less than its length prefix
+                LengthMismatch { expected: usize, actual: usize },
+                /// bytes that are not a value of the named type
+                InvalidValue(&'static str),
+                /// bytes left after the decoded value
+                TrailingBytes(usize),
+            }
+
+            impl ::std::fmt::Display for DecodeError {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                    match self {
+                        DecodeError::UnexpectedEnd { needed } => {
+                            write!(f, "unexpected end of input, {} more bytes needed", needed)
+                        },
+                        DecodeError::UnknownTag {
+                            ty,
+                            tag: ::std::option::Option::Some(tag),
+                        } => write!(f, "unknown tag {} for `{}`", tag, ty),
+                        DecodeError::UnknownTag { ty, tag: _ } => {
+                            write!(f, "unknown tag above `i128::MAX` for `{}`", ty)
+                        },
+                        DecodeError::LengthMismatch { expected, actual } => write!(
+                            f,
+                            "payload of {} bytes decoded from {} bytes",
+                            expected, actual
+                        ),
+                        DecodeError::InvalidValue(ty) => write!(f, "invalid `{}`", ty),
+                        DecodeError::TrailingBytes(len) => {
+                            write!(f, "{} bytes left after the value", len)
+                        },
+                    }
+                }
+            }
+
+            impl ::std::error::Error for DecodeError {}
+
+            #[derive(Debug, Clone, PartialEq, Eq)]
+            pub enum EncodeError {
+                /// a payload of `len` bytes does not fit its length prefix
+                PayloadTooLong { len: usize },
+                /// a `str`, slice or `Vec` of `len` items, above `u32::MAX`
+                TooManyItems { len: usize },
+            }
+
+            impl ::std::fmt::Display for EncodeError {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                    match self {
+                        EncodeError::PayloadTooLong { len } => {
+                            write!(f, "payload of {} bytes overflows its length", len)
+                        },
+                        EncodeError::TooManyItems { len } => {
+                            write!(f, "length {} overflows `u32`", len)
+                        },
+                    }
+                }
+            }
+
+            impl ::std::error::Error for EncodeError {}
+
+            pub trait Encode {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError>;
+
+                fn to_bytes(&self) -> ::std::result::Result<::std::vec::Vec<u8>, EncodeError> {
+                    let mut out = ::std::vec::Vec::new();
+                    self.encode(&mut out)?;
+                    ::std::result::Result::Ok(out)
+                }
+            }
+
+            pub trait Decode: Sized {
+                /// decode from the front of `input`, advancing it
+                fn decode(input: &mut &[u8]) -> ::std::result::Result<Self, DecodeError>;
+
+                /// decode the whole of `bytes`
+                fn from_bytes(mut bytes: &[u8]) -> ::std::result::Result<Self, DecodeError> {
+                    let value = Self::decode(&mut bytes)?;
+                    if bytes.is_empty() {
+                        ::std::result::Result::Ok(value)
+                    } else {
+                        ::std::result::Result::Err(DecodeError::TrailingBytes(bytes.len()))
+                    }
+                }
+            }
+
+            /// split `len` bytes off the front of `input`
+            pub fn take<'a>(
+                input: &mut &'a [u8],
+                len: usize,
+            ) -> ::std::result::Result<&'a [u8], DecodeError> {
+                if input.len() < len {
+                    return ::std::result::Result::Err(DecodeError::UnexpectedEnd {
+                        needed: len - input.len(),
+                    });
+                }
+
+                let (head, rest) = input.split_at(len);
+                *input = rest;
+                ::std::result::Result::Ok(head)
+            }
+
+            /// the payload written by `f`, prefixed by its length as `L`
+            #[doc(hidden)]
+            pub fn encode_content<L, F>(
+                out: &mut ::std::vec::Vec<u8>,
+                f: F,
+            ) -> ::std::result::Result<(), EncodeError>
+            where
+                L: Encode + ::std::convert::TryFrom<usize>,
+                F: FnOnce(&mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError>,
+            {
+                let mut content = ::std::vec::Vec::new();
+                f(&mut content)?;
+                L::try_from(content.len())
+                    .map_err(|_| EncodeError::PayloadTooLong { len: content.len() })?
+                    .encode(out)?;
+                out.extend_from_slice(&content);
+                ::std::result::Result::Ok(())
+            }
+
+            /// decode a payload with `f`, checking it uses all of its length
+            #[doc(hidden)]
+            pub fn decode_content<L, T, F>(
+                input: &mut &[u8],
+                f: F,
+            ) -> ::std::result::Result<T, DecodeError>
+            where
+                L: Decode + ::std::convert::TryInto<usize>,
+                F: FnOnce(&mut &[u8]) -> ::std::result::Result<T, DecodeError>,
+            {
+                let len = L::decode(input)?
+                    .try_into()
+                    .map_err(|_| DecodeError::InvalidValue("length"))?;
+                let mut content = take(input, len)?;
+                let value = f(&mut content)?;
+                if content.is_empty() {
+                    ::std::result::Result::Ok(value)
+                } else {
+                    ::std::result::Result::Err(DecodeError::LengthMismatch {
+                        expected: len,
+                        actual: len - content.len(),
+                    })
+                }
+            }
+
+            // lengths of `str`, slices and `Vec` are `u32`
+            fn encode_len(
+                len: usize,
+                out: &mut ::std::vec::Vec<u8>,
+            ) -> ::std::result::Result<(), EncodeError> {
+                <u32 as ::std::convert::TryFrom<usize>>::try_from(len)
+                    .map_err(|_| EncodeError::TooManyItems { len })?
+                    .encode(out)
+            }
+
+            fn decode_len(input: &mut &[u8]) -> ::std::result::Result<usize, DecodeError> {
+                let len = u32::decode(input)?;
+                <usize as ::std::convert::TryFrom<u32>>::try_from(len)
+                    .map_err(|_| DecodeError::InvalidValue("length"))
+            }
+
+            #(
+                impl Encode for #ints {
+                    fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                        out.extend_from_slice(&self.to_le_bytes());
+                        ::std::result::Result::Ok(())
+                    }
+                }
+
+                impl Decode for #ints {
+                    fn decode(input: &mut &[u8]) -> ::std::result::Result<Self, DecodeError> {
+                        let bytes = take(input, ::std::mem::size_of::<#ints>())?;
+                        let mut raw = [0; ::std::mem::size_of::<#ints>()];
+                        raw.copy_from_slice(bytes);
+                        ::std::result::Result::Ok(<#ints>::from_le_bytes(raw))
+                    }
+                }
+            )*
+
+            // pointer sized integers are 64 bits on every target
+            impl Encode for usize {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    (*self as u64).encode(out)
+                }
+            }
+
+            impl Decode for usize {
+                fn decode(input: &mut &[u8]) -> ::std::result::Result<Self, DecodeError> {
+                    <usize as ::std::convert::TryFrom<u64>>::try_from(u64::decode(input)?)
+                        .map_err(|_| DecodeError::InvalidValue("usize"))
+                }
+            }
+
+            impl Encode for isize {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    (*self as i64).encode(out)
+                }
+            }
+
+            impl Decode for isize {
+                fn decode(input: &mut &[u8]) -> ::std::result::Result<Self, DecodeError> {
+                    <isize as ::std::convert::TryFrom<i64>>::try_from(i64::decode(input)?)
+                        .map_err(|_| DecodeError::InvalidValue("isize"))
+                }
+            }
+
+            impl Encode for f32 {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    self.to_bits().encode(out)
+                }
+            }
+
+            impl Decode for f32 {
+                fn decode(input: &mut &[u8]) -> ::std::result::Result<Self, DecodeError> {
+                    u32::decode(input).map(f32::from_bits)
+                }
+            }
+
+            impl Encode for f64 {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    self.to_bits().encode(out)
+                }
+            }
 
-        let disp = lit_from_token(&fields.ident)
-            .map(|l| quote! { #[enum_repr(discr = #l)] })
-            .unwrap_or(quote! {});
+            impl Decode for f64 {
+                fn decode(input: &mut &[u8]) -> ::std::result::Result<Self, DecodeError> {
+                    u64::decode(input).map(f64::from_bits)
+                }
+            }
 
-        let (eq, e) = match &fields.discriminant {
-            Some((eq, e)) => (Some(eq), Some(e)),
-            _ => (None, None),
+            impl Encode for bool {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    out.push(*self as u8);
+                    ::std::result::Result::Ok(())
+                }
+            }
+
+            impl Decode for bool {
+                fn decode(input: &mut &[u8]) -> ::std::result::Result<Self, DecodeError> {
+                    match u8::decode(input)? {
+                        0 => ::std::result::Result::Ok(false),
+                        1 => ::std::result::Result::Ok(true),
+                        _ => ::std::result::Result::Err(DecodeError::InvalidValue("bool")),
+                    }
+                }
+            }
+
+            impl Encode for char {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    (*self as u32).encode(out)
+                }
+            }
+
+            impl Decode for char {
+                fn decode(input: &mut &[u8]) -> ::std::result::Result<Self, DecodeError> {
+                    ::std::char::from_u32(u32::decode(input)?)
+                        .ok_or(DecodeError::InvalidValue("char"))
+                }
+            }
+
+            impl Encode for () {
+                fn encode(&self, _: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    ::std::result::Result::Ok(())
+                }
+            }
+
+            impl Decode for () {
+                fn decode(_: &mut &[u8]) -> ::std::result::Result<Self, DecodeError> {
+                    ::std::result::Result::Ok(())
+                }
+            }
+
+            impl Encode for str {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    encode_len(self.len(), out)?;
+                    out.extend_from_slice(self.as_bytes());
+                    ::std::result::Result::Ok(())
+                }
+            }
+
+            impl Encode for ::std::string::String {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    self.as_str().encode(out)
+                }
+            }
+
+            impl Decode for ::std::string::String {
+                fn decode(input: &mut &[u8]) -> ::std::result::Result<Self, DecodeError> {
+                    let len = decode_len(input)?;
+                    let bytes = take(input, len)?.to_vec();
+                    ::std::string::String::from_utf8(bytes)
+                        .map_err(|_| DecodeError::InvalidValue("String"))
+                }
+            }
+
+            impl<T: Encode> Encode for [T] {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    encode_len(self.len(), out)?;
+                    for item in self {
+                        item.encode(out)?;
+                    }
+                    ::std::result::Result::Ok(())
+                }
+            }
+
+            impl<T: Encode> Encode for ::std::vec::Vec<T> {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    self.as_slice().encode(out)
+                }
+            }
+
+            impl<T: Decode> Decode for ::std::vec::Vec<T> {
+                fn decode(input: &mut &[u8]) -> ::std::result::Result<Self, DecodeError> {
+                    let len = decode_len(input)?;
+                    // a corrupt length must not reserve more than the input
+                    let mut items = ::std::vec::Vec::with_capacity(len.min(input.len()));
+                    for _ in 0..len {
+                        items.push(T::decode(input)?);
+                    }
+                    ::std::result::Result::Ok(items)
+                }
+            }
+
+            // fixed length, no prefix
+            impl<T: Encode, const N: usize> Encode for [T; N] {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    for item in self {
+                        item.encode(out)?;
+                    }
+                    ::std::result::Result::Ok(())
+                }
+            }
+
+            impl<T: Decode, const N: usize> Decode for [T; N] {
+                fn decode(input: &mut &[u8]) -> ::std::result::Result<Self, DecodeError> {
+                    let mut items = ::std::vec::Vec::with_capacity(N);
+                    for _ in 0..N {
+                        items.push(T::decode(input)?);
+                    }
+                    <[T; N] as ::std::convert::TryFrom<::std::vec::Vec<T>>>::try_from(items)
+                        .map_err(|_| DecodeError::InvalidValue("array"))
+                }
+            }
+
+            impl<T: Encode> Encode for ::std::option::Option<T> {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    match self {
+                        ::std::option::Option::Some(value) => {
+                            out.push(1);
+                            value.encode(out)
+                        },
+                        ::std::option::Option::None => {
+                            out.push(0);
+                            ::std::result::Result::Ok(())
+                        },
+                    }
+                }
+            }
+
+            impl<T: Decode> Decode for ::std::option::Option<T> {
+                fn decode(input: &mut &[u8]) -> ::std::result::Result<Self, DecodeError> {
+                    match u8::decode(input)? {
+                        0 => ::std::result::Result::Ok(::std::option::Option::None),
+                        1 => T::decode(input).map(::std::option::Option::Some),
+                        _ => ::std::result::Result::Err(DecodeError::InvalidValue("Option")),
+                    }
+                }
+            }
+
+            impl<T: Encode + ?Sized> Encode for ::std::boxed::Box<T> {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    (**self).encode(out)
+                }
+            }
+
+            impl<T: Decode> Decode for ::std::boxed::Box<T> {
+                fn decode(input: &mut &[u8]) -> ::std::result::Result<Self, DecodeError> {
+                    T::decode(input).map(::std::boxed::Box::new)
+                }
+            }
+
+            impl<'a, T: Encode + ?Sized> Encode for &'a T {
+                fn encode(&self, out: &mut ::std::vec::Vec<u8>) -> ::std::result::Result<(), EncodeError> {
+                    (**self).encode(out)
+                }
+            }
+        }
+    };
+
+    debug_expansion("codec", ident, &tokens, &input, &output);
+    Ok(output)
+}
+
+/// run the derives listed on the item that this crate provides
+pub fn expand_to_string(input: TokenStream) -> Result<TokenStream> {
+    let item = syn::parse2::<DeriveInput>(input.clone())?;
+    let mut output = TokenStream::new();
+    let mut found = false;
+
+    let attrs = &item.attrs;
+    for attr in take!(attrs, "derive") {
+        let paths = match attr.parse_meta()? {
+            Meta::List(list) => list.nested,
+            meta => return err!(meta: "expected #[derive(..)]"),
+        };
+
+        for path in paths {
+            let name = match &path {
+                NestedMeta::Meta(Meta::Path(p)) => p.segments.last(),
+                _ => None,
+            };
+            let expand = match name {
+                Some(s) if s.ident == "EnumRepr" => derive::enum_repr,
+                Some(s) if s.ident == "Accessors" => derive::accessors,
+                Some(s) if s.ident == "Newtype" => derive::newtype,
+                Some(s) if s.ident == "Tagged" => derive::tagged,
+                _ => continue,
+            };
+
+            output.extend(expand(input.clone())?);
+            found = true;
+        }
+    }
+
+    if !found {
+        let ident = &item.ident;
+        return err!(ident: "expected #[derive(EnumRepr)], #[derive(Accessors)], \
+                 #[derive(Newtype)] or #[derive(Tagged)]");
+    }
+
+    let expanded = format_tokens(output);
+    Ok(quote! { #expanded })
+}
+
+pub fn make_enum(tokens: TokenStream) -> Result<TokenStream> {
+    let input = syn::parse2::<EnumsMacro>(tokens.clone())?;
+    split!(input as module, enums, convs);
+
+    let enums = enums
+        .iter()
+        .map(make_one_enum)
+        .collect::<Result<Vec<_>>>()?;
+
+    let convs = convs
+        .iter()
+        .map(|conv| make_enum_conv(&input, conv))
+        .collect::<Result<Vec<_>>>()?;
+
+    let output = match module {
+        Some(module) => {
+            split!(module as attrs, vis, ident);
+
+            quote! {
+                #(#attrs)*
+                #vis mod #ident {
+                    #[allow(unused_imports)]
+                    use super::*;
+
+                    #(#enums)*
+                    #(#convs)*
+                }
+            }
+        },
+        None => quote! {
+            #(#enums)*
+            #(#convs)*
+        },
+    };
+
+    let name = match module {
+        Some(module) => &module.ident,
+        None => &input.enums[0].ident,
+    };
+    debug_expansion("make_enum", name, &tokens, &input, &output);
+
+    Ok(output)
+}
+
+fn make_one_enum(input: &EnumMacro) -> Result<TokenStream> {
+    split!(input as attrs, vis, ident, default, variants);
+
+    let repr = input.repr();
+    let default = match default {
+        Some((_, default)) => quote! {
+            impl ::std::default::Default for #ident {
+                fn default() -> Self {
+                    #ident::#default
+                }
+            }
+        },
+        None => quote! {},
+    };
+
+    let values = input.values()?;
+    let declare_variants = variants.iter().zip(&values).map(|(v, value)| {
+        split!(v as attrs, ident, fields);
+
+        let discriminant = match (&fields.discriminant, value) {
+            (Some((eq, e)), _) => quote! { #eq #e },
+            (None, (Some(value), true)) => {
+                let value = int_lit(*value);
+                quote! { = #value }
+            },
+            _ => quote! {},
         };
 
         quote! {
             #(#attrs)*
-             #disp
-             #ident #eq #e,
+             #ident #discriminant,
         }
     });
 
+    let docs = make_enum_docs(input, &values)?;
+    let consts = make_enum_consts(input)?;
+    let columns = make_enum_columns(input);
+
+    // rebuild whenever the variants file changes
+    let source = match &input.source {
+        Some(source) => {
+            let path = source.full_path()?;
+            let path = path.to_string_lossy();
+
+            quote! {
+                const _: &[u8] = include_bytes!(#path);
+            }
+        },
+        None => quote! {},
+    };
+
     let output = quote! {
         #(#attrs)*
+        #docs
         #[repr(#repr)]
         #[derive(EnumRepr)]
-        #[enum_repr(default = #default)]
         #vis enum #ident {
             #(#declare_variants)*
         }
+
+        #default
+        #consts
+        #columns
+        #source
     };
 
     Ok(output)
 }
+
+/// markdown table of variants, constants, values and string names appended
+/// to the enum docs
+fn make_enum_docs(
+    input: &EnumMacro,
+    values: &[(Option<i128>, bool)],
+) -> Result<TokenStream> {
+    split!(input as attrs, ident, variants);
+
+    let (bits, _) = repr_bits(&input.raw_repr());
+    let strict = deny_unknown_fields(attrs)?;
+    let container = get_container_attrs::<EnumReprAttr>(attrs.clone())?
+        .into_iter()
+        .map(|EnumReprAttr(a)| a)
+        .collect();
+
+    let mut lines = vec![
+        String::new(),
+        "# Values".to_owned(),
+        String::new(),
+        "| Variant | Constant | Value | Hex | Name |".to_owned(),
+        "|---------|----------|------:|----:|------|".to_owned(),
+    ];
+
+    for (v, (value, _)) in variants.iter().zip(values) {
+        let var = get_variant_attrs::<VariantAttr>(v.attrs.clone(), strict)?;
+        let name = variant_name(&container, &var, &v.ident);
+
+        let constant = match &v.fields.ident {
+            Some(c) => format!("`{}`", c),
+            None => String::new(),
+        };
+
+        let (dec, hex) = match (value, &v.fields.discriminant) {
+            (Some(value), _) => {
+                let raw = match bits {
+                    128 => *value as u128,
+                    _ if *value < 0 => (*value + (1 << bits)) as u128,
+                    _ => *value as u128,
+                };
+                (value.to_string(), format!("`{:#x}`", raw))
+            },
+            (None, Some((_, e))) => {
+                (format!("`{}`", e.to_token_stream()), String::new())
+            },
+            (None, None) => (String::new(), String::new()),
+        };
+
+        lines.push(format!(
+            "| [`{var}`]({id}::{var}) | {} | {} | {} | `\"{}\"` |",
+            constant,
+            dec,
+            hex,
+            name.replace('|', "\\|"),
+            var = v.ident,
+            id = ident,
+        ));
+    }
+
+    Ok(quote! {
+        #(#[doc = #lines])*
+    })
+}
+
+/// associated `Self` constants plus raw integer constants in a companion
+/// module, one pair per `Variant(CONST_NAME)`
+fn make_enum_consts(input: &EnumMacro) -> Result<TokenStream> {
+    split!(input as vis, ident, variants);
+
+    let mut seen = HashSet::new();
+    for name in input.const_names() {
+        if !seen.insert(name) {
+            return err!(name: "duplicate constant name `{}`", name);
+        }
+    }
+
+    if seen.is_empty() {
+        return Ok(quote! {});
+    }
+
+    let raw = input.raw_repr();
+    let consts_mod = input.consts_mod();
+
+    let (assoc, raws): (Vec<_>, Vec<_>) = variants
+        .iter()
+        .filter_map(|v| v.fields.ident.as_ref().map(|name| (v, name)))
+        .map(|(v, name)| {
+            let attrs = &v.attrs;
+            let docs = take!(attrs, "doc")
+                .chain(take_cfg!(attrs))
+                .collect::<Vec<_>>();
+            let var = &v.ident;
+
+            (
+                quote! {
+                    #(#docs)*
+                    pub const #name: Self = #ident::#var;
+                },
+                quote! {
+                    #(#docs)*
+                    pub const #name: #raw = super::#ident::#var as #raw;
+                },
+            )
+        })
+        .unzip();
+
+    let mod_doc = format!("Raw `{}` values of [`{}`] constants.", raw, ident);
+
+    Ok(quote! {
+        #[allow(dead_code)]
+        impl #ident {
+            #(#assoc)*
+        }
+
+        #[doc = #mod_doc]
+        #[allow(dead_code)]
+        #vis mod #consts_mod {
+            #(#raws)*
+        }
+    })
+}
+
+/// one accessor per `columns(name: Type)`, matching on the variant values
+fn make_enum_columns(input: &EnumMacro) -> TokenStream {
+    split!(input as ident, columns, variants);
+
+    if columns.is_empty() {
+        return quote! {};
+    }
+
+    let accessors = columns.iter().map(|column| {
+        let name = &column.ident;
+        let ty = &column.ty;
+        let doc = format!("`{}` column of [`{}`].", name, ident);
+
+        let arms = variants.iter().map(|v| {
+            let attrs = &v.attrs;
+            let cfgs = take_cfg!(attrs);
+            let var = &v.ident;
+            let value = v
+                .fields
+                .values
+                .iter()
+                .find(|value| value.ident == *name)
+                .map(|value| &value.expr);
+
+            quote! {
+                #(#cfgs)*
+                #ident::#var => #value,
+            }
+        });
+
+        quote! {
+            #[doc = #doc]
+            pub fn #name(&self) -> #ty {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    });
+
+    quote! {
+        #[allow(dead_code)]
+        impl #ident {
+            #(#accessors)*
+        }
+    }
+}
+
+/// `From`/`TryFrom` between two enums, pairing variants by constant name
+fn make_enum_conv(input: &EnumsMacro, conv: &ConvMacro) -> Result<TokenStream> {
+    let find = |ident: &Ident| input.enums.iter().find(|e| e.ident == *ident);
+    let (source, target) = match (find(&conv.source), find(&conv.target)) {
+        (Some(source), Some(target)) => (source, target),
+        _ => unreachable!("conversions are checked while parsing"),
+    };
+
+    let from = &source.ident;
+    let to = &target.ident;
+
+    let mut arms = Vec::new();
+    let mut unpaired = false;
+
+    for v in &source.variants {
+        let paired = v
+            .fields
+            .ident
+            .as_ref()
+            .and_then(|name| target.variant_by_const(name));
+
+        let var = &v.ident;
+        let attrs = &v.attrs;
+        let cfgs = take_cfg!(attrs);
+
+        match paired {
+            Some(t) if conv.is_try() => {
+                let t = &t.ident;
+                arms.push(quote! {
+                    #(#cfgs)* #from::#var => ::std::result::Result::Ok(#to::#t),
+                });
+            },
+            Some(t) => {
+                let t = &t.ident;
+                arms.push(quote! { #(#cfgs)* #from::#var => #to::#t, });
+            },
+            None if conv.is_try() => unpaired = true,
+            None => {
+                return err!(
+                    var: "variant `{}::{}` has no counterpart in `{}`, \
+                          use `impl TryFrom<{}> for {};` instead",
+                    from,
+                    var,
+                    to,
+                    from,
+                    to
+                );
+            },
+        }
+    }
+
+    if !conv.is_try() {
+        return Ok(quote! {
+            impl ::std::convert::From<#from> for #to {
+                fn from(v: #from) -> Self {
+                    match v {
+                        #(#arms)*
+                    }
+                }
+            }
+        });
+    }
+
+    let rest = if unpaired {
+        quote! { v => ::std::result::Result::Err(v), }
+    } else {
+        quote! {}
+    };
+
+    Ok(quote! {
+        impl ::std::convert::TryFrom<#from> for #to {
+            type Error = #from;
+
+            fn try_from(v: #from) -> ::std::result::Result<Self, #from> {
+                match v {
+                    #(#arms)*
+                    #rest
+                }
+            }
+        }
+    })
+}
+
+/// unsuffixed integer literal, negative values as `-lit`
+fn int_lit(value: i128) -> TokenStream {
+    let lit = proc_macro2::Literal::u128_unsuffixed(value.unsigned_abs());
+    if value < 0 {
+        quote! { -#lit }
+    } else {
+        quote! { #lit }
+    }
+}
@@ -16,12 +16,28 @@ mod ast;
 mod attr;
 mod derive;
 mod func;
+mod pretty;
 
 call!(
     #[proc_macro_derive(EnumRepr, attributes(enum_repr))]
     derive::enum_repr
 );
 
+call!(
+    #[proc_macro_derive(Accessors, attributes(enum_repr))]
+    derive::accessors
+);
+
+call!(
+    #[proc_macro_derive(Newtype, attributes(enum_repr))]
+    derive::newtype
+);
+
+call!(
+    #[proc_macro_derive(Tagged, attributes(enum_repr))]
+    derive::tagged
+);
+
 call!(
     #[proc_macro_attribute]
     attr::awe(attr: TokenStream)
@@ -32,7 +48,40 @@ call!(
     func::forward_impl
 );
 
+call!(
+    #[proc_macro]
+    func::impl_many
+);
+
 call!(
     #[proc_macro]
     func::make_enum
 );
+
+call!(
+    #[proc_macro]
+    func::codec
+);
+
+call!(
+    /// the derives of `mac_derive` applied to an item, as a `&'static str`
+    /// of the formatted expansion for snapshot tests
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate mac_derive;
+    ///
+    /// fn main() {
+    ///     let expanded = expand_to_string! {
+    ///         #[derive(Accessors)]
+    ///         struct Point {
+    ///             x: i32,
+    ///         }
+    ///     };
+    ///
+    ///     assert!(expanded.contains("pub fn x(&self) -> &i32 {"));
+    /// }
+    /// ```
+    #[proc_macro]
+    func::expand_to_string
+);
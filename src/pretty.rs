@@ -0,0 +1,340 @@
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+
+/// keywords keeping a space before `(`, `::` and `!`
+const KEYWORDS: &[&str] = &[
+    "as", "break", "const", "crate", "dyn", "else", "enum", "fn", "for", "if",
+    "impl", "in", "let", "match", "mod", "move", "mut", "ref", "return",
+    "static", "struct", "trait", "type", "unsafe", "use", "where", "while",
+];
+
+/// deterministic rendering of generated code, one item, statement, field or
+/// arm per line with blocks indented by four spaces
+///
+/// unlike `rustfmt` it needs nothing outside the compiler, so the output is
+/// stable enough for snapshots
+pub fn format_tokens(tokens: TokenStream) -> String {
+    let mut f = Formatter {
+        out: String::new(),
+        depth: 0,
+        angle: 0,
+        prev: Prev::Start,
+    };
+
+    f.stream(tokens, true);
+
+    let mut out = f.out.trim_end().to_owned();
+    out.push('\n');
+    out
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Prev {
+    Start,
+    Open,
+    Close(Delimiter),
+    /// `true` for keywords
+    Ident(bool),
+    Literal,
+    Punct(char, Spacing),
+    /// second colon of `::`
+    PathSep,
+    GenericOpen,
+    GenericClose,
+}
+
+struct Formatter {
+    out: String,
+    depth: usize,
+    /// open `<` of generics in the current group
+    angle: usize,
+    prev: Prev,
+}
+
+impl Formatter {
+    fn stream(&mut self, tokens: TokenStream, block: bool) {
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            self.token(token, tokens.peek(), block);
+        }
+    }
+
+    fn token(
+        &mut self,
+        token: TokenTree,
+        next: Option<&TokenTree>,
+        block: bool,
+    ) {
+        match token {
+            TokenTree::Group(group) => {
+                let delimiter = group.delimiter();
+                let attr = self.prev == Prev::Punct('#', Spacing::Alone);
+
+                if delimiter == Delimiter::None {
+                    return self.stream(group.stream(), block);
+                }
+
+                if self.space_before_group(delimiter) {
+                    self.push(" ");
+                }
+
+                let angle = self.angle;
+                self.angle = 0;
+
+                match delimiter {
+                    Delimiter::Brace if group.stream().is_empty() => {
+                        self.push("{}");
+                    },
+                    Delimiter::Brace => {
+                        self.push("{");
+                        self.depth += 1;
+                        self.newline();
+                        self.prev = Prev::Start;
+                        self.stream(group.stream(), true);
+                        self.depth -= 1;
+                        self.newline();
+                        self.push("}");
+                    },
+                    _ => {
+                        let (open, close) = match delimiter {
+                            Delimiter::Parenthesis => ("(", ")"),
+                            _ => ("[", "]"),
+                        };
+                        self.push(open);
+                        self.prev = Prev::Open;
+                        self.stream(group.stream(), false);
+                        self.push(close);
+                    },
+                }
+
+                self.angle = angle;
+                self.prev = Prev::Close(delimiter);
+
+                let continued = match next {
+                    Some(TokenTree::Punct(p)) => ",;.?".contains(p.as_char()),
+                    Some(TokenTree::Ident(i)) => i == "else",
+                    _ => false,
+                };
+
+                if block
+                    && ((delimiter == Delimiter::Brace && !continued)
+                        || (delimiter == Delimiter::Bracket && attr))
+                {
+                    self.newline();
+                }
+            },
+            TokenTree::Ident(ident) => {
+                let ident = ident.to_string();
+                if self.space_before(None) {
+                    self.push(" ");
+                }
+                self.push(&ident);
+                self.prev = Prev::Ident(KEYWORDS.contains(&ident.as_str()));
+            },
+            TokenTree::Literal(lit) => {
+                if self.space_before(None) {
+                    self.push(" ");
+                }
+                self.push(&lit.to_string());
+                self.prev = Prev::Literal;
+            },
+            TokenTree::Punct(p) => {
+                let ch = p.as_char();
+                let after_arrow = match self.prev {
+                    Prev::Punct(c, Spacing::Joint) => c == '-' || c == '=',
+                    _ => false,
+                };
+
+                if ch == '<' && self.generic_open() {
+                    if !matches!(self.prev, Prev::Ident(_) | Prev::PathSep)
+                        && self.space_before(Some(&p))
+                    {
+                        self.push(" ");
+                    }
+                    self.push("<");
+                    self.angle += 1;
+                    self.prev = Prev::GenericOpen;
+                    return;
+                }
+
+                if ch == '>' && self.angle > 0 && !after_arrow {
+                    self.push(">");
+                    self.angle -= 1;
+                    self.prev = Prev::GenericClose;
+                    return;
+                }
+
+                if self.space_before(Some(&p)) {
+                    self.push(" ");
+                }
+                self.push(&ch.to_string());
+
+                self.prev = match (ch, self.prev) {
+                    (':', Prev::Punct(':', Spacing::Joint)) => Prev::PathSep,
+                    _ => Prev::Punct(ch, p.spacing()),
+                };
+
+                if block && (ch == ';' || (ch == ',' && self.angle == 0)) {
+                    self.newline();
+                }
+            },
+        }
+    }
+
+    /// `<` opens generics unless it follows a value
+    fn generic_open(&self) -> bool {
+        !matches!(
+            self.prev,
+            Prev::Literal
+                | Prev::Close(Delimiter::Parenthesis)
+                | Prev::Close(Delimiter::Bracket)
+        )
+    }
+
+    fn space_before(&self, next: Option<&proc_macro2::Punct>) -> bool {
+        if self.out.is_empty() || self.out.ends_with('\n') {
+            return false;
+        }
+
+        match self.prev {
+            Prev::Start | Prev::Open | Prev::PathSep | Prev::GenericOpen => {
+                return false
+            },
+            Prev::Punct(_, Spacing::Joint) => return false,
+            Prev::Punct(c, _) if "#&!.$".contains(c) => return false,
+            _ => {},
+        }
+
+        let next = match next {
+            Some(next) => next,
+            None => return true,
+        };
+
+        match next.as_char() {
+            ',' | ';' | '.' | '?' => false,
+            ':' if next.spacing() == Spacing::Joint => {
+                !matches!(self.prev, Prev::Ident(false) | Prev::GenericClose)
+            },
+            ':' => false,
+            '!' => !matches!(self.prev, Prev::Ident(false)),
+            _ => true,
+        }
+    }
+
+    fn space_before_group(&self, delimiter: Delimiter) -> bool {
+        if self.out.is_empty() || self.out.ends_with('\n') {
+            return false;
+        }
+
+        if delimiter == Delimiter::Brace {
+            return !matches!(self.prev, Prev::Open | Prev::Start);
+        }
+
+        !matches!(
+            self.prev,
+            Prev::Start
+                | Prev::Open
+                | Prev::PathSep
+                | Prev::GenericOpen
+                | Prev::GenericClose
+                | Prev::Ident(false)
+                | Prev::Close(Delimiter::Parenthesis)
+                | Prev::Close(Delimiter::Bracket)
+                | Prev::Punct('#', _)
+                | Prev::Punct('!', _)
+                | Prev::Punct('&', _)
+                | Prev::Punct(_, Spacing::Joint)
+        )
+    }
+
+    /// text at the current position, indenting fresh lines
+    fn push(&mut self, text: &str) {
+        if self.out.ends_with('\n') {
+            for _ in 0..self.depth {
+                self.out.push_str("    ");
+            }
+        }
+        self.out.push_str(text);
+    }
+
+    fn newline(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_tokens;
+    use func::expand_to_string;
+    use syn::LitStr;
+
+    #[test]
+    fn layout() {
+        let tokens = quote! {
+            #[derive(Debug)]
+            pub struct Point<T> { x: T, y: Vec<Option<T>> }
+
+            impl<T: Copy> Point<T> where T: Default {
+                pub fn x(&self) -> T { if true { self.x } else { T::default() } }
+                fn empty() {}
+            }
+        };
+
+        assert_eq!(
+            format_tokens(tokens),
+            "\
+#[derive(Debug)]
+pub struct Point<T> {
+    x: T,
+    y: Vec<Option<T>>
+}
+impl<T: Copy> Point<T> where T: Default {
+    pub fn x(&self) -> T {
+        if true {
+            self.x
+        } else {
+            T::default()
+        }
+    }
+    fn empty() {}
+}
+"
+        );
+    }
+
+    #[test]
+    fn snapshot() {
+        let expanded = expand_to_string(quote! {
+            #[derive(Accessors)]
+            struct Point {
+                x: i32,
+            }
+        })
+        .unwrap();
+        let expanded = syn::parse2::<LitStr>(expanded).unwrap().value();
+
+        assert_eq!(
+            expanded,
+            "\
+#[allow(dead_code)]
+impl Point {
+    pub fn x(&self) -> &i32 {
+        &self.x
+    }
+    pub fn x_mut(&mut self) -> &mut i32 {
+        &mut self.x
+    }
+    pub fn set_x(&mut self, value: i32) -> &mut Self {
+        self.x = value;
+        self
+    }
+    pub fn with_x(mut self, value: i32) -> Self {
+        self.x = value;
+        self
+    }
+}
+"
+        );
+    }
+}
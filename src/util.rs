@@ -1,7 +1,16 @@
 #![allow(unused_macros)]
 
+use pretty::format_tokens;
 pub use proc_macro2::{Span, TokenStream};
-pub use syn::{Error, Ident};
+use std::{
+    collections::hash_map::DefaultHasher,
+    env,
+    fmt::{Debug, Display},
+    fs,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+};
+pub use syn::Ident;
 
 macro_rules! count {
     ($head:expr $(, $tail:expr)*) => {  1 + $crate::count!($($tail),*) };
@@ -93,15 +102,56 @@ macro_rules! take_cfg {
     };
 }
 
-macro_rules! dump {
-	( $( $( $arg:tt );+) +) => {
-        $(
-    		println!("{:>10} {} => [", "-", stringify!( $( $arg )* ));
-    		println!("{:#?}", $($arg)*);
-    		println!("]");
-    		println!("{:>30}", "-");
-        )+
-	};
+/// directory receiving one file per expansion when set
+pub const DEBUG_ENV: &str = "MAC_DERIVE_DEBUG";
+
+/// write `input`, the `parsed` form and `output` of one expansion to
+/// `$MAC_DERIVE_DEBUG/<crate>.<macro>.<item>.<hash>.rs`
+///
+/// the hash of the input tells apart same-named items of different modules
+///
+/// debugging must never break a build, so every I/O error is ignored; cargo
+/// does not track the variable, touch a source file to expand again
+pub fn debug_expansion(
+    macro_: &str,
+    item: &dyn Display,
+    input: &TokenStream,
+    parsed: &dyn Debug,
+    output: &TokenStream,
+) {
+    let dir = match env::var_os(DEBUG_ENV) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => return,
+    };
+
+    let krate = env::var("CARGO_CRATE_NAME").unwrap_or_default();
+    let mut hasher = DefaultHasher::new();
+    input.to_string().hash(&mut hasher);
+    let hash = hasher.finish() as u32;
+
+    let name = format!("{}.{}.{}.{:08x}.rs", krate, macro_, item, hash)
+        .replace(|c: char| !c.is_alphanumeric() && c != '.' && c != '_', "_");
+
+    let parsed = format!("{:#?}", parsed)
+        .lines()
+        .map(|line| format!("// {}\n", line))
+        .collect::<String>();
+
+    let content = format!(
+        "// `{}` expansion of `{}` in crate `{}`\n\n\
+         // ---- input ----\n\n{}\n\
+         // ---- parsed ----\n\n{}\n\
+         // ---- output ----\n\n{}",
+        macro_,
+        item,
+        krate,
+        format_tokens(input.clone()),
+        parsed,
+        format_tokens(output.clone()),
+    );
+
+    let _ = fs::create_dir_all(&dir)
+        .and_then(|_| fs::write(dir.join(name), content));
 }
 
 pub fn wrap_in_const(
@@ -121,13 +171,15 @@ pub fn wrap_in_const(
         },
         None => quote! {
             #[allow(unknown_lints)]
-            #[cfg_attr(feature = "cargo-clippy", allow(useless_attribute))]
+            #[allow(clippy::useless_attribute)]
             #[allow(rust_2018_idioms)]
             extern crate mac_derive as _derive;
         },
     };
 
+    // impls inside a named const trip `non_local_definitions`
     quote! {
+        #[allow(unknown_lints, non_local_definitions)]
         #[allow(non_upper_case_globals)]
         #[allow(unused_attributes)]
         #[allow(unused_qualifications)]